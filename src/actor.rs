@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// `ReplicaId` wraps an actor identifier so it gets its own type.
+///
+/// A plain `String` actor unifies with any other `String`, so nothing stops
+/// a user-id from being used as an actor in a CRDT expecting replica-ids.
+/// Parameterize CRDT's over `ReplicaId` instead, optionally with a `Tag`
+/// type, to have the compiler catch such mixups.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{actor::ReplicaId, CmRDT, PNCounter};
+///
+/// let mut a: PNCounter<ReplicaId<String>> = PNCounter::new();
+/// a.apply(a.inc(ReplicaId::new("replica-1".to_string())));
+/// assert_eq!(a.read(), 1.into());
+/// ```
+///
+/// Differently tagged ids don't unify:
+///
+/// ```compile_fail
+/// use crdts::{actor::ReplicaId, CmRDT, GCounter};
+///
+/// struct Replicas;
+/// struct Users;
+///
+/// let counter: GCounter<ReplicaId<String, Replicas>> = GCounter::new();
+/// let user: ReplicaId<String, Users> = ReplicaId::new("alice".to_string());
+/// counter.inc(user);
+/// ```
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ReplicaId<T, Tag = ()> {
+    id: T,
+    #[serde(skip)]
+    tag: PhantomData<fn() -> Tag>,
+}
+
+impl<T, Tag> ReplicaId<T, Tag> {
+    /// Wrap an actor identifier.
+    pub fn new(id: T) -> Self {
+        ReplicaId {
+            id,
+            tag: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the wrapped identifier.
+    pub fn get(&self) -> &T {
+        &self.id
+    }
+
+    /// Unwraps the identifier.
+    pub fn into_inner(self) -> T {
+        self.id
+    }
+}
+
+impl<T: Clone, Tag> Clone for ReplicaId<T, Tag> {
+    fn clone(&self) -> Self {
+        ReplicaId::new(self.id.clone())
+    }
+}
+
+impl<T: Copy, Tag> Copy for ReplicaId<T, Tag> {}
+
+impl<T: PartialEq, Tag> PartialEq for ReplicaId<T, Tag> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T: Eq, Tag> Eq for ReplicaId<T, Tag> {}
+
+impl<T: PartialOrd, Tag> PartialOrd for ReplicaId<T, Tag> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.id.partial_cmp(&other.id)
+    }
+}
+
+impl<T: Ord, Tag> Ord for ReplicaId<T, Tag> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl<T: Hash, Tag> Hash for ReplicaId<T, Tag> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl<T: Debug, Tag> Debug for ReplicaId<T, Tag> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReplicaId").field(&self.id).finish()
+    }
+}
+
+impl<T: Display, Tag> Display for ReplicaId<T, Tag> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.id, f)
+    }
+}
+
+impl<T, Tag> From<T> for ReplicaId<T, Tag> {
+    fn from(id: T) -> Self {
+        ReplicaId::new(id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{Actor, CmRDT, CvRDT, GCounter};
+
+    fn assert_actor<A: Actor>() {}
+
+    #[test]
+    fn test_replica_id_is_an_actor() {
+        struct Replicas;
+        assert_actor::<ReplicaId<String>>();
+        assert_actor::<ReplicaId<u64, Replicas>>();
+
+        let mut a: GCounter<ReplicaId<u64, Replicas>> = GCounter::new();
+        let mut b = a.clone();
+        a.apply(a.inc(1.into()));
+        b.apply(b.inc(2.into()));
+        a.merge(b);
+        assert_eq!(a.read(), 2u8.into());
+    }
+
+    #[test]
+    fn test_replica_id_serializes_as_inner_id() {
+        let id: ReplicaId<String> = ReplicaId::new("A".to_string());
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, "\"A\"");
+        assert_eq!(
+            serde_json::from_str::<ReplicaId<String>>(&json).unwrap(),
+            id
+        );
+    }
+}
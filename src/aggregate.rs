@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use num_bigint::BigInt;
+use serde::{Deserialize, Serialize};
+
+use crate::{pncounter, traits::VacuousValidation, CmRDT, CvRDT, PNCounter};
+
+/// `AggregateCounter` rolls up a `PNCounter` per shard into a total.
+///
+/// Each shard key `K` holds its own counter, merges are shard-wise, and the
+/// value is the sum of all shards.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{aggregate::AggregateCounter, CmRDT};
+///
+/// let mut a = AggregateCounter::new();
+/// a.apply(a.inc("eu", "A"));
+/// a.apply(a.inc("us", "A"));
+/// a.apply(a.dec("us", "A"));
+///
+/// assert_eq!(a.value(), 1.into());
+/// assert_eq!(a.shard_value(&"us"), 0.into());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AggregateCounter<A: Ord, K: Ord> {
+    shards: BTreeMap<K, PNCounter<A>>,
+}
+
+/// An op updating one shard of an `AggregateCounter`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Op<K, A: Ord> {
+    /// The shard to update
+    pub shard: K,
+    /// The op to apply to the shard's counter
+    pub op: pncounter::Op<A>,
+}
+
+impl<A: Ord, K: Ord> Default for AggregateCounter<A, K> {
+    fn default() -> Self {
+        Self {
+            shards: BTreeMap::new(),
+        }
+    }
+}
+
+impl<A: Ord + Clone + Debug, K: Ord> CmRDT for AggregateCounter<A, K> {
+    type Op = Op<K, A>;
+    type Validation = VacuousValidation;
+
+    fn validate_op(&self, _op: &Self::Op) -> Result<(), Self::Validation> {
+        Ok(())
+    }
+
+    fn apply(&mut self, Op { shard, op }: Self::Op) {
+        self.shards.entry(shard).or_default().apply(op)
+    }
+}
+
+impl<A: Ord + Clone + Debug, K: Ord> CvRDT for AggregateCounter<A, K> {
+    type Validation = VacuousValidation;
+
+    fn validate_merge(&self, _other: &Self) -> Result<(), Self::Validation> {
+        Ok(())
+    }
+
+    fn merge(&mut self, other: Self) {
+        for (shard, counter) in other.shards {
+            self.shards.entry(shard).or_default().merge(counter);
+        }
+    }
+}
+
+impl<A: Ord + Clone, K: Ord> AggregateCounter<A, K> {
+    /// Returns a new `AggregateCounter` with no shards.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Generate an op to increment the counter of this shard.
+    pub fn inc(&self, shard: K, actor: A) -> Op<K, A> {
+        let op = match self.shard(&shard) {
+            Some(counter) => counter.inc(actor),
+            None => PNCounter::new().inc(actor),
+        };
+        Op { shard, op }
+    }
+
+    /// Generate an op to decrement the counter of this shard.
+    pub fn dec(&self, shard: K, actor: A) -> Op<K, A> {
+        let op = match self.shard(&shard) {
+            Some(counter) => counter.dec(actor),
+            None => PNCounter::new().dec(actor),
+        };
+        Op { shard, op }
+    }
+
+    /// Returns the counter of this shard, if it was ever updated.
+    pub fn shard(&self, shard: &K) -> Option<&PNCounter<A>> {
+        self.shards.get(shard)
+    }
+
+    /// Returns the value of this shard, 0 if it was never updated.
+    pub fn shard_value(&self, shard: &K) -> BigInt {
+        self.shard(shard)
+            .map(PNCounter::read)
+            .unwrap_or_else(|| BigInt::from(0))
+    }
+
+    /// Returns an iterator over the shards that were ever updated, in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &PNCounter<A>)> {
+        self.shards.iter()
+    }
+
+    /// Returns the sum of the values of all shards.
+    pub fn value(&self) -> BigInt {
+        self.shards.values().map(PNCounter::read).sum()
+    }
+}
+
+/// `CounterMap` holds many counters named by a string label, like the
+/// labels of a metric, each converging independently.
+///
+/// It is an `AggregateCounter` keyed by `String` with label oriented
+/// accessors, merges are label-wise.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{aggregate::CounterMap, CmRDT};
+///
+/// let mut a = CounterMap::new();
+/// a.apply(a.inc("requests", "A"));
+/// a.apply(a.inc("errors", "A"));
+/// a.apply(a.dec("errors", "A"));
+///
+/// assert_eq!(a.value("requests"), 1.into());
+/// assert_eq!(a.value("errors"), 0.into());
+/// assert_eq!(a.iter().map(|(label, _)| label).collect::<Vec<_>>(), vec!["errors", "requests"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CounterMap<A: Ord> {
+    counters: AggregateCounter<A, String>,
+}
+
+impl<A: Ord> Default for CounterMap<A> {
+    fn default() -> Self {
+        Self {
+            counters: AggregateCounter::default(),
+        }
+    }
+}
+
+impl<A: Ord + Clone + Debug> CmRDT for CounterMap<A> {
+    type Op = Op<String, A>;
+    type Validation = VacuousValidation;
+
+    fn validate_op(&self, op: &Self::Op) -> Result<(), Self::Validation> {
+        self.counters.validate_op(op)
+    }
+
+    fn apply(&mut self, op: Self::Op) {
+        self.counters.apply(op)
+    }
+}
+
+impl<A: Ord + Clone + Debug> CvRDT for CounterMap<A> {
+    type Validation = VacuousValidation;
+
+    fn validate_merge(&self, other: &Self) -> Result<(), Self::Validation> {
+        self.counters.validate_merge(&other.counters)
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.counters.merge(other.counters)
+    }
+}
+
+impl<A: Ord + Clone> CounterMap<A> {
+    /// Returns a new `CounterMap` with no labels.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Generate an op to increment the counter of this label.
+    pub fn inc(&self, label: impl Into<String>, actor: A) -> Op<String, A> {
+        self.counters.inc(label.into(), actor)
+    }
+
+    /// Generate an op to decrement the counter of this label.
+    pub fn dec(&self, label: impl Into<String>, actor: A) -> Op<String, A> {
+        self.counters.dec(label.into(), actor)
+    }
+
+    /// Returns the counter of this label, if it was ever updated.
+    pub fn get(&self, label: &str) -> Option<&PNCounter<A>> {
+        self.counters.shards.get(label)
+    }
+
+    /// Returns the value of this label, 0 if it was never updated.
+    pub fn value(&self, label: &str) -> BigInt {
+        self.get(label)
+            .map(PNCounter::read)
+            .unwrap_or_else(|| BigInt::from(0))
+    }
+
+    /// Returns an iterator over the labels that were ever updated and their
+    /// counters, in label order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PNCounter<A>)> {
+        self.counters
+            .iter()
+            .map(|(label, counter)| (label.as_str(), counter))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_shard_updates_converge() {
+        let mut a = AggregateCounter::new();
+        let mut b = AggregateCounter::new();
+
+        a.apply(a.inc("shard-1", "A"));
+        a.apply(a.inc("shard-2", "A"));
+        b.apply(b.inc("shard-1", "B"));
+        b.apply(b.dec("shard-2", "B"));
+        b.apply(b.dec("shard-2", "B"));
+
+        let mut a_then_b = a.clone();
+        a_then_b.merge(b.clone());
+        b.merge(a);
+        assert_eq!(a_then_b, b);
+
+        assert_eq!(b.shard_value(&"shard-1"), 2.into());
+        assert_eq!(b.shard_value(&"shard-2"), (-1).into());
+        assert_eq!(b.shard_value(&"shard-3"), 0.into());
+        assert_eq!(b.value(), 1.into());
+    }
+
+    #[test]
+    fn test_counter_map_merges_label_wise() {
+        let mut a = CounterMap::new();
+        let mut b = CounterMap::new();
+
+        a.apply(a.inc("hits", "A"));
+        a.apply(a.inc("misses", "A"));
+        b.apply(b.inc("hits", "B"));
+        b.apply(b.dec("misses", "B"));
+        b.apply(b.dec("misses", "B"));
+
+        a.merge(b);
+        assert_eq!(a.value("hits"), 2.into());
+        assert_eq!(a.value("misses"), (-1).into());
+        assert_eq!(a.value("other"), 0.into());
+        assert_eq!(a.iter().count(), 2);
+    }
+}
@@ -0,0 +1,85 @@
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::CmRDT;
+
+/// Check that `expected` is exactly the state produced by replaying `ops`
+/// onto a fresh instance, both in log order and in a shuffled order.
+///
+/// The shuffle is seeded, so the same log is always checked against the
+/// same order. Ops of counters commute so any replay order must converge,
+/// a log that only reaches `expected` in one order has been tampered with.
+/// Don't use this for CRDT's like `Orswot` whose ops must be applied in
+/// causal order, a shuffled replay of a valid log may legitimately differ.
+///
+/// Counter ops carry the actor's cumulative count, so an op followed by a
+/// later op of the same actor has no effect on the final state. Dropping
+/// or altering such an op is not detected.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{audit::verify_replay, CmRDT, PNCounter};
+///
+/// let mut counter = PNCounter::new();
+/// let mut log = Vec::new();
+/// for op in vec![counter.inc("A"), counter.dec("B")] {
+///     counter.apply(op.clone());
+///     log.push(op);
+/// }
+/// assert!(verify_replay(&log, &counter));
+/// ```
+pub fn verify_replay<T>(ops: &[T::Op], expected: &T) -> bool
+where
+    T: CmRDT + PartialEq + Default,
+    T::Op: Clone,
+{
+    let replay = |ops: &[T::Op]| {
+        let mut state = T::default();
+        for op in ops {
+            state.apply(op.clone());
+        }
+        state
+    };
+
+    let mut shuffled = ops.to_vec();
+    shuffled.shuffle(&mut StdRng::seed_from_u64(ops.len() as u64));
+
+    &replay(ops) == expected && &replay(&shuffled) == expected
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{pncounter, PNCounter};
+
+    #[test]
+    fn test_verify_replay_detects_tampering() {
+        let mut counter = PNCounter::new();
+        let mut log = Vec::new();
+        for i in 0u8..20 {
+            let op = if i % 3 == 0 {
+                counter.dec(i % 4)
+            } else {
+                counter.inc(i % 4)
+            };
+            counter.apply(op.clone());
+            log.push(op);
+        }
+        assert!(verify_replay(&log, &counter));
+
+        // dropping the latest op reaches a different state
+        let mut truncated = log.clone();
+        truncated.pop();
+        assert!(!verify_replay(&truncated, &counter));
+
+        // flipping an op's direction reaches a different state
+        let mut flipped = log;
+        let last = flipped.last_mut().unwrap();
+        last.dir = match last.dir {
+            pncounter::Dir::Pos => pncounter::Dir::Neg,
+            pncounter::Dir::Neg => pncounter::Dir::Pos,
+        };
+        assert!(!verify_replay(&flipped, &counter));
+    }
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+use crate::VClock;
+
+/// `CausalOp` is an op tagged with the clock it was generated against, for
+/// transports that enforce causal delivery without a separate queue.
+///
+/// A receiver should hold the op back until it has delivered everything in
+/// `deps`, i.e. until `is_ready` returns `true` for its delivered clock.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{CmRDT, GCounter, VClock};
+///
+/// let mut a = GCounter::new();
+/// let first = a.inc("A");
+/// a.apply(first.clone());
+///
+/// let mut seen = VClock::new();
+/// seen.apply(first.clone());
+/// let second = a.inc_with_deps("A", seen);
+///
+/// let mut delivered = VClock::new();
+/// assert!(!second.is_ready(&delivered));
+/// delivered.apply(first);
+/// assert!(second.is_ready(&delivered));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CausalOp<O, A: Ord> {
+    op: O,
+    deps: VClock<A>,
+}
+
+impl<O, A: Ord> CausalOp<O, A> {
+    /// Tag `op` with the clock it depends on.
+    pub fn new(op: O, deps: VClock<A>) -> Self {
+        Self { op, deps }
+    }
+
+    /// Returns the op.
+    pub fn op(&self) -> &O {
+        &self.op
+    }
+
+    /// Returns the clock the op was generated against.
+    pub fn deps(&self) -> &VClock<A> {
+        &self.deps
+    }
+
+    /// Returns true if a receiver that has delivered `clock` may apply the op.
+    pub fn is_ready(&self, clock: &VClock<A>) -> bool {
+        self.deps <= *clock
+    }
+
+    /// Unwraps the op.
+    pub fn into_op(self) -> O {
+        self.op
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{CmRDT, Dot, PNCounter};
+
+    #[test]
+    fn test_receiver_holds_back_op_until_deps_delivered() {
+        let mut a = PNCounter::new();
+        let inc = a.inc("A");
+        a.apply(inc.clone());
+        let dec = a.dec("A");
+        a.apply(dec.clone());
+
+        let deps: VClock<_> = vec![Dot::new("A", 1)].into_iter().collect();
+        let op = a.inc_with_deps("A", deps.clone());
+        assert_eq!(op.deps(), &deps);
+        a.apply(op.op().clone());
+
+        // a receiver tracking the actors' delivered ops in a clock
+        let mut b = PNCounter::new();
+        let mut delivered = VClock::new();
+        assert!(!op.is_ready(&delivered));
+
+        b.apply(inc);
+        delivered.apply(Dot::new("A", 1));
+        assert!(op.is_ready(&delivered));
+
+        b.apply(op.into_op());
+        b.apply(dec);
+        assert_eq!(b, a);
+    }
+}
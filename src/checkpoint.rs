@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+
+use crate::CanonicalBytes;
+
+/// `Checkpoints` records digests of a replica's state under labels, so two
+/// replicas can later be checked to have reached the same states.
+///
+/// Intended for soak and integration tests: record both replicas at the
+/// same labels, e.g. after each round of exchanging ops, then compare.
+/// The digests are taken over the state's `CanonicalBytes`, so they don't
+/// depend on hash map iteration order and can be compared across processes.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use crdts::{checkpoint::Checkpoints, CmRDT, GCounter};
+///
+/// let mut a = GCounter::new();
+/// let mut b = GCounter::new();
+/// let (mut a_checkpoints, mut b_checkpoints) = (Checkpoints::new(), Checkpoints::new());
+///
+/// let op = a.inc("A");
+/// a.apply(op.clone());
+/// b.apply(op);
+///
+/// a_checkpoints.record("round-1", &a);
+/// b_checkpoints.record("round-1", &b);
+/// a_checkpoints.assert_converged(&b_checkpoints);
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Checkpoints {
+    digests: BTreeMap<String, u64>,
+}
+
+impl Checkpoints {
+    /// Returns a new empty `Checkpoints`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Record the digest of the state under this label, replacing any
+    /// digest previously recorded under it.
+    pub fn record<T: CanonicalBytes>(&mut self, label: impl Into<String>, state: &T) {
+        self.digests.insert(label.into(), digest(state));
+    }
+
+    /// Returns the digest recorded under this label.
+    pub fn digest(&self, label: &str) -> Option<u64> {
+        self.digests.get(label).cloned()
+    }
+
+    /// Returns the labels recorded by both whose digests differ, in order.
+    pub fn diverged<'a>(&'a self, other: &Checkpoints) -> Vec<&'a str> {
+        self.digests
+            .iter()
+            .filter(|(label, digest)| {
+                other
+                    .digests
+                    .get(label.as_str())
+                    .map(|other_digest| other_digest != *digest)
+                    .unwrap_or(false)
+            })
+            .map(|(label, _)| label.as_str())
+            .collect()
+    }
+
+    /// Panics if any label recorded by both has differing digests.
+    /// Labels recorded by only one of them are ignored.
+    pub fn assert_converged(&self, other: &Checkpoints) {
+        let diverged = self.diverged(other);
+        assert!(
+            diverged.is_empty(),
+            "replicas diverged at checkpoints {:?}",
+            diverged
+        );
+    }
+}
+
+/// The 64 bit FNV-1a hash of the state's canonical bytes. Unlike the std
+/// hashers it is fixed, so digests stay comparable across processes and
+/// Rust releases.
+pub(crate) fn digest<T: CanonicalBytes>(state: &T) -> u64 {
+    state
+        .canonical_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+#[cfg(all(test, feature = "json"))]
+mod test {
+    use super::*;
+
+    use crate::{CmRDT, CvRDT, Orswot, PNCounter, VClock};
+
+    #[test]
+    fn test_replicas_converge_at_checkpoints() {
+        let mut a = PNCounter::new();
+        let mut b = PNCounter::new();
+        let mut a_checkpoints = Checkpoints::new();
+        let mut b_checkpoints = Checkpoints::new();
+
+        a.apply(a.inc("A"));
+        b.apply(b.dec("B"));
+        a_checkpoints.record("diverged", &a);
+        b_checkpoints.record("diverged", &b);
+        b_checkpoints.record("only-b", &b);
+        assert_eq!(a_checkpoints.diverged(&b_checkpoints), vec!["diverged"]);
+
+        a.merge(b.clone());
+        b.merge(a.clone());
+        a_checkpoints.record("synced", &a);
+        b_checkpoints.record("synced", &b);
+        assert_eq!(
+            a_checkpoints.digest("synced"),
+            b_checkpoints.digest("synced")
+        );
+        assert_eq!(b_checkpoints.diverged(&a_checkpoints), vec!["diverged"]);
+
+        a_checkpoints.record("diverged", &a);
+        b_checkpoints.record("diverged", &b);
+        a_checkpoints.assert_converged(&b_checkpoints);
+    }
+
+    #[test]
+    #[should_panic(expected = "replicas diverged at checkpoints [\"round-1\"]")]
+    fn test_assert_converged_panics_on_divergence() {
+        let mut a = PNCounter::new();
+        a.apply(a.inc("A"));
+        let mut a_checkpoints = Checkpoints::new();
+        let mut b_checkpoints = Checkpoints::new();
+        a_checkpoints.record("round-1", &a);
+        b_checkpoints.record("round-1", &PNCounter::<&str>::new());
+        a_checkpoints.assert_converged(&b_checkpoints);
+    }
+
+    #[test]
+    fn test_orswot_checkpoints_ignore_insertion_order() {
+        let mut a = Orswot::new();
+        let mut b = Orswot::new();
+        for member in 0..16u8 {
+            a.apply(a.add(member, a.read_ctx().derive_add_ctx("A")));
+        }
+        for member in (0..16u8).rev() {
+            b.merge(a.clone());
+            b.apply(b.rm(member, b.contains(&member).derive_rm_ctx()));
+        }
+        a.merge(b.clone());
+
+        let (mut a_checkpoints, mut b_checkpoints) = (Checkpoints::new(), Checkpoints::new());
+        a_checkpoints.record("synced", &a);
+        b_checkpoints.record("synced", &b);
+        a_checkpoints.assert_converged(&b_checkpoints);
+    }
+
+    #[test]
+    fn test_digest_is_stable() {
+        assert_eq!(digest(&VClock::<u8>::new()), 0x0961_2b07_b5ec_b5a5);
+    }
+}
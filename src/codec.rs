@@ -0,0 +1,252 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::{self, Display};
+#[cfg(feature = "json")]
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "json")]
+use crate::{CmRDT, CrdtError};
+
+/// Frames longer than this are rejected by `apply_stream` instead of
+/// being buffered, ops are never anywhere near this large.
+#[cfg(feature = "json")]
+const MAX_FRAME_LEN: usize = 1 << 20;
+
+/// The serialization formats CRDT's can be encoded with.
+///
+/// `Json`, `Cbor` and `Bincode` are enabled by the `json`, `cbor` and
+/// `bincode` features, the module is only compiled in with at least one of
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Format {
+    /// JSON, via `serde_json`
+    #[cfg(feature = "json")]
+    Json,
+    /// CBOR, via `serde_cbor`
+    #[cfg(feature = "cbor")]
+    Cbor,
+    /// Bincode, via `bincode`
+    #[cfg(feature = "bincode")]
+    Bincode,
+}
+
+/// The error returned when encoding or decoding fails.
+#[derive(Debug)]
+pub enum CodecError {
+    /// A JSON encoding error
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// A CBOR encoding error
+    #[cfg(feature = "cbor")]
+    Cbor(serde_cbor::Error),
+    /// A Bincode encoding error
+    #[cfg(feature = "bincode")]
+    Bincode(bincode::Error),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "json")]
+            CodecError::Json(err) => write!(f, "json: {}", err),
+            #[cfg(feature = "cbor")]
+            CodecError::Cbor(err) => write!(f, "cbor: {}", err),
+            #[cfg(feature = "bincode")]
+            CodecError::Bincode(err) => write!(f, "bincode: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "json")]
+            CodecError::Json(err) => Some(err),
+            #[cfg(feature = "cbor")]
+            CodecError::Cbor(err) => Some(err),
+            #[cfg(feature = "bincode")]
+            CodecError::Bincode(err) => Some(err),
+        }
+    }
+}
+
+/// Encode a value in the given format.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use crdts::{codec::{self, Format}, CmRDT, GCounter};
+///
+/// let mut a = GCounter::new();
+/// a.apply(a.inc(1u32));
+///
+/// let bytes = codec::encode(&a, Format::Json).unwrap();
+/// let b: GCounter<u32> = codec::decode(&bytes, Format::Json).unwrap();
+/// assert_eq!(a, b);
+/// # }
+/// ```
+pub fn encode<T: Serialize>(value: &T, format: Format) -> Result<Vec<u8>, CodecError> {
+    match format {
+        #[cfg(feature = "json")]
+        Format::Json => serde_json::to_vec(value).map_err(CodecError::Json),
+        #[cfg(feature = "cbor")]
+        Format::Cbor => serde_cbor::to_vec(value).map_err(CodecError::Cbor),
+        #[cfg(feature = "bincode")]
+        Format::Bincode => bincode::serialize(value).map_err(CodecError::Bincode),
+    }
+}
+
+/// Decode a value encoded with `encode` in the given format.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8], format: Format) -> Result<T, CodecError> {
+    match format {
+        #[cfg(feature = "json")]
+        Format::Json => serde_json::from_slice(bytes).map_err(CodecError::Json),
+        #[cfg(feature = "cbor")]
+        Format::Cbor => serde_cbor::from_slice(bytes).map_err(CodecError::Cbor),
+        #[cfg(feature = "bincode")]
+        Format::Bincode => bincode::deserialize(bytes).map_err(CodecError::Bincode),
+    }
+}
+
+/// Write `value` as one frame of an op stream: its length as a big-endian
+/// `u32` followed by its JSON encoding.
+///
+/// Streams of such frames are read back by `GCounter::apply_stream` and
+/// `PNCounter::apply_stream`.
+#[cfg(feature = "json")]
+pub fn write_delimited<W: Write, T: Serialize>(mut writer: W, value: &T) -> io::Result<()> {
+    let bytes = serde_json::to_vec(value).map_err(io::Error::from)?;
+    if bytes.len() > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "frame too long",
+        ));
+    }
+    writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+    writer.write_all(&bytes)
+}
+
+/// Apply the frames of `reader` to `crdt` one at a time, only a single
+/// frame is buffered at once. A clean end of input between frames ends
+/// the stream, anything else that goes wrong stops it early; the ops
+/// applied up to that point are kept.
+#[cfg(feature = "json")]
+pub(crate) fn apply_stream<T, R, A>(crdt: &mut T, mut reader: R) -> Result<usize, CrdtError<A>>
+where
+    T: CmRDT,
+    T::Op: DeserializeOwned,
+    R: Read,
+{
+    let mut applied = 0;
+    let mut buf = Vec::new();
+    loop {
+        let mut len = [0u8; 4];
+        match read_frame_header(&mut reader, &mut len) {
+            Ok(false) => return Ok(applied),
+            Ok(true) => (),
+            Err(err) => return Err(CrdtError::Io(err.kind())),
+        }
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(CrdtError::MalformedOp { index: applied });
+        }
+        buf.resize(len, 0);
+        reader
+            .read_exact(&mut buf)
+            .map_err(|err| CrdtError::Io(err.kind()))?;
+        let op =
+            serde_json::from_slice(&buf).map_err(|_| CrdtError::MalformedOp { index: applied })?;
+        crdt.apply(op);
+        applied += 1;
+    }
+}
+
+/// Fill `header`, returning `false` if the input ended before its first byte.
+#[cfg(feature = "json")]
+fn read_frame_header<R: Read>(reader: &mut R, header: &mut [u8; 4]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{CmRDT, PNCounter};
+
+    fn enabled_formats() -> Vec<Format> {
+        vec![
+            #[cfg(feature = "json")]
+            Format::Json,
+            #[cfg(feature = "cbor")]
+            Format::Cbor,
+            #[cfg(feature = "bincode")]
+            Format::Bincode,
+        ]
+    }
+
+    #[test]
+    fn test_pncounter_round_trips_in_every_format() {
+        let mut a = PNCounter::new();
+        a.apply(a.inc_many((1u16, 2u16), 7));
+        a.apply(a.dec((3, 4)));
+
+        for format in enabled_formats() {
+            let bytes = encode(&a, format).unwrap();
+            let decoded: PNCounter<(u16, u16)> = decode(&bytes, format).unwrap();
+            assert_eq!(decoded, a, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_decode_garbage_fails() {
+        for format in enabled_formats() {
+            assert!(decode::<PNCounter<u8>>(&[0xff, 0x00, 0x13], format).is_err());
+        }
+    }
+
+    #[test]
+    fn test_vclock_dots_written_as_a_map_still_decode() {
+        #[derive(Serialize)]
+        struct MapClock {
+            dots: std::collections::BTreeMap<u8, u64>,
+        }
+        let old = MapClock {
+            dots: vec![(1, 4), (2, 0), (3, 1)].into_iter().collect(),
+        };
+
+        let expected: crate::VClock<u8> = vec![crate::Dot::new(1, 4), crate::Dot::new(3, 1)]
+            .into_iter()
+            .collect();
+        for format in enabled_formats() {
+            let bytes = encode(&old, format).unwrap();
+            let decoded: crate::VClock<u8> = decode(&bytes, format).unwrap();
+            assert_eq!(decoded, expected, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_error_source_is_the_format_error() {
+        use std::error::Error;
+
+        for format in enabled_formats() {
+            let err = decode::<PNCounter<u8>>(&[0xff, 0x00, 0x13], format).unwrap_err();
+            let source = err.source().expect("format error");
+            assert!(
+                err.to_string().ends_with(&source.to_string()),
+                "{:?}",
+                format
+            );
+        }
+    }
+}
@@ -38,6 +38,25 @@ pub struct RmCtx<A: Ord> {
     pub clock: VClock<A>,
 }
 
+impl<V, A: Ord> ReadCtx<V, A> {
+    /// Splits the value read from the context it was read under, so the value
+    /// can be used to compute a write while the context is kept to derive the
+    /// write's Add/RmCtx, tying the write to what was read.
+    pub fn split(self) -> (V, ReadCtx<(), A>) {
+        let ReadCtx {
+            add_clock,
+            rm_clock,
+            val,
+        } = self;
+        let ctx = ReadCtx {
+            add_clock,
+            rm_clock,
+            val: (),
+        };
+        (val, ctx)
+    }
+}
+
 impl<V, A: Ord + Clone + Debug> ReadCtx<V, A> {
     /// Derives an AddCtx for a given actor from a ReadCtx
     pub fn derive_add_ctx(self, actor: A) -> AddCtx<A> {
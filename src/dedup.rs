@@ -0,0 +1,386 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+
+use crate::map::Val;
+use crate::{lseq, map, orswot, pncounter, CmRDT, CvRDT, Dot, VClock};
+
+/// `MergeDeduper` skips merging states that were already merged recently.
+///
+/// In gossip it's common to receive the very same state from several peers
+/// in one round. Merging is idempotent so re-merging is harmless, but it's
+/// wasted work. The deduper remembers the last `window` distinct states it
+/// merged and skips any incoming state equal to one of them.
+///
+/// States are compared by their hash first and only on a hash match by
+/// equality, so a hash collision can never cause a distinct state to be
+/// dropped.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{CmRDT, GCounter, dedup::MergeDeduper};
+///
+/// let mut deduper = MergeDeduper::new(8);
+/// let mut local = GCounter::new();
+///
+/// let mut gossip = GCounter::new();
+/// gossip.apply(gossip.inc("A"));
+///
+/// assert!(deduper.merge(&mut local, gossip.clone()));
+/// assert!(!deduper.merge(&mut local, gossip));
+/// assert_eq!(deduper.skipped(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MergeDeduper<T> {
+    window: usize,
+    recent: VecDeque<(u64, T)>,
+    skipped: usize,
+}
+
+impl<T: CvRDT + Hash + Eq + Clone> MergeDeduper<T> {
+    /// Create a deduper remembering up to `window` recently merged states.
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            recent: VecDeque::with_capacity(window),
+            skipped: 0,
+        }
+    }
+
+    /// Merge `other` into `target` unless an identical state was merged
+    /// within the window.
+    ///
+    /// Returns `true` if the merge was performed, `false` if it was skipped.
+    pub fn merge(&mut self, target: &mut T, other: T) -> bool {
+        let digest = digest(&other);
+        if self
+            .recent
+            .iter()
+            .any(|(seen_digest, seen)| *seen_digest == digest && seen == &other)
+        {
+            self.skipped += 1;
+            return false;
+        }
+
+        if self.window > 0 {
+            if self.recent.len() == self.window {
+                self.recent.pop_front();
+            }
+            self.recent.push_back((digest, other.clone()));
+        }
+        target.merge(other);
+        true
+    }
+
+    /// The number of merges skipped so far.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Forget all recently merged states, e.g. at the end of a gossip round.
+    pub fn clear(&mut self) {
+        self.recent.clear();
+    }
+}
+
+/// Ops that are witnessed by a dot.
+pub trait Witnessed<A: Ord> {
+    /// The dot witnessing this op, `None` for ops without one, e.g. removes.
+    fn witness(&self) -> Option<&Dot<A>>;
+}
+
+impl<A: Ord> Witnessed<A> for Dot<A> {
+    fn witness(&self) -> Option<&Dot<A>> {
+        Some(self)
+    }
+}
+
+impl<A: Ord> Witnessed<A> for pncounter::Op<A> {
+    fn witness(&self) -> Option<&Dot<A>> {
+        Some(&self.dot)
+    }
+}
+
+impl<M, A: Ord> Witnessed<A> for orswot::Op<M, A> {
+    fn witness(&self) -> Option<&Dot<A>> {
+        match self {
+            orswot::Op::Add { dot, .. } | orswot::Op::Rename { dot, .. } => Some(dot),
+            orswot::Op::Rm { .. } => None,
+        }
+    }
+}
+
+impl<K: Ord, V: Val<A>, A: Ord> Witnessed<A> for map::Op<K, V, A> {
+    fn witness(&self) -> Option<&Dot<A>> {
+        match self {
+            map::Op::Up { dot, .. } => Some(dot),
+            map::Op::Rm { .. } => None,
+        }
+    }
+}
+
+impl<T, A: Ord> Witnessed<A> for lseq::Op<T, A> {
+    fn witness(&self) -> Option<&Dot<A>> {
+        Some(self.dot())
+    }
+}
+
+/// `Deduped` wraps an op-based CRDT and drops redelivered ops before they
+/// reach `apply`.
+///
+/// The ops of each actor must be delivered in the order they were produced,
+/// as `CmRDT` already requires, an op whose dot we've already seen is then
+/// a duplicate. Ops without a dot are always applied.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{CmRDT, GCounter, dedup::Deduped};
+///
+/// let mut counter = Deduped::new(GCounter::new());
+/// let op = counter.inner().inc("A");
+///
+/// assert!(counter.apply_once(op.clone()));
+/// assert!(!counter.apply_once(op));
+/// assert_eq!(counter.inner().read(), 1u8.into());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deduped<T, A: Ord> {
+    crdt: T,
+    seen: VClock<A>,
+}
+
+impl<T: CmRDT, A: Ord + Clone + Debug> Deduped<T, A>
+where
+    T::Op: Witnessed<A>,
+{
+    /// Wrap a CRDT, no ops have been seen yet.
+    pub fn new(crdt: T) -> Self {
+        Self {
+            crdt,
+            seen: VClock::new(),
+        }
+    }
+
+    /// Apply the op unless its dot has already been seen.
+    ///
+    /// Returns `true` if the op was applied, `false` if it was dropped.
+    pub fn apply_once(&mut self, op: T::Op) -> bool {
+        if let Some(dot) = op.witness() {
+            if self.seen.dominates_dot(dot) {
+                return false;
+            }
+            self.seen.apply(dot.clone());
+        }
+        self.crdt.apply(op);
+        true
+    }
+
+    /// The clock of the dots seen so far.
+    pub fn seen(&self) -> &VClock<A> {
+        &self.seen
+    }
+
+    /// Returns a reference to the wrapped CRDT.
+    pub fn inner(&self) -> &T {
+        &self.crdt
+    }
+
+    /// Unwraps the CRDT.
+    pub fn into_inner(self) -> T {
+        self.crdt
+    }
+}
+
+/// `AppliedOps` records the dots of applied ops, refusing to mark a dot
+/// twice, e.g. to guard ops that aren't idempotent against redelivery.
+///
+/// Unlike `Deduped`, the ops of an actor may be marked in any order: a
+/// dot marked past a gap in its actor's dots is remembered on its own
+/// until the gap is filled, so the missing dots are still new.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{dedup::AppliedOps, Dot};
+///
+/// let mut applied = AppliedOps::new();
+/// assert!(applied.mark_and_check(Dot::new("A", 1)));
+/// assert!(!applied.mark_and_check(Dot::new("A", 1)));
+///
+/// assert!(applied.mark_and_check(Dot::new("A", 3)));
+/// assert!(applied.mark_and_check(Dot::new("A", 2)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AppliedOps<A: Ord> {
+    applied: VClock<A>,
+    pending: BTreeMap<A, BTreeSet<u64>>,
+}
+
+impl<A: Ord> Default for AppliedOps<A> {
+    fn default() -> Self {
+        Self {
+            applied: VClock::new(),
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+impl<A: Ord + Clone + Debug> AppliedOps<A> {
+    /// Returns a registry with no applied ops.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Mark the op witnessed by `dot` as applied.
+    ///
+    /// Returns `true` if the op is new and should be applied, `false` if
+    /// it was already applied.
+    pub fn mark_and_check(&mut self, dot: Dot<A>) -> bool {
+        if self.is_applied(&dot) {
+            return false;
+        }
+        let next = self.applied.get(&dot.actor).checked_add(1);
+        if next.is_some_and(|next| dot.counter > next) {
+            // there's a gap, remember the dot until it's filled
+            self.pending
+                .entry(dot.actor)
+                .or_default()
+                .insert(dot.counter);
+            return true;
+        }
+
+        let mut counter = dot.counter;
+        if let Some(pending) = self.pending.get_mut(&dot.actor) {
+            // the dot at `u64::MAX` has no successor to pull in
+            while let Some(next) = counter.checked_add(1).filter(|next| pending.remove(next)) {
+                counter = next;
+            }
+            if pending.is_empty() {
+                self.pending.remove(&dot.actor);
+            }
+        }
+        self.applied.apply(Dot::new(dot.actor, counter));
+        true
+    }
+
+    /// Returns true if the op witnessed by `dot` was already applied.
+    pub fn is_applied(&self, dot: &Dot<A>) -> bool {
+        self.applied.dominates_dot(dot)
+            || self
+                .pending
+                .get(&dot.actor)
+                .map(|pending| pending.contains(&dot.counter))
+                .unwrap_or(false)
+    }
+
+    /// The clock of the dots applied without gaps: every dot it covers has
+    /// been marked. Dots marked past a gap aren't included.
+    pub fn clock(&self) -> &VClock<A> {
+        &self.applied
+    }
+}
+
+pub(crate) fn digest<T: Hash>(state: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{GCounter, Orswot};
+
+    #[test]
+    fn test_identical_state_is_skipped() {
+        let mut deduper = MergeDeduper::new(2);
+        let mut local = GCounter::new();
+
+        let mut a = GCounter::new();
+        a.apply(a.inc("A"));
+        let mut b = GCounter::new();
+        b.apply(b.inc("B"));
+
+        assert!(deduper.merge(&mut local, a.clone()));
+        assert!(!deduper.merge(&mut local, a.clone()));
+        assert!(deduper.merge(&mut local, b.clone()));
+        assert_eq!(deduper.skipped(), 1);
+        assert_eq!(local.read(), 2u8.into());
+
+        deduper.clear();
+        assert!(deduper.merge(&mut local, a));
+    }
+
+    #[test]
+    fn test_window_evicts_oldest() {
+        let mut deduper = MergeDeduper::new(1);
+        let mut local = GCounter::new();
+
+        let mut a = GCounter::new();
+        a.apply(a.inc("A"));
+        let mut b = GCounter::new();
+        b.apply(b.inc("B"));
+
+        assert!(deduper.merge(&mut local, a.clone()));
+        assert!(deduper.merge(&mut local, b));
+        assert!(deduper.merge(&mut local, a));
+        assert_eq!(deduper.skipped(), 0);
+    }
+
+    #[test]
+    fn test_redelivered_op_is_skipped() {
+        let mut set = Deduped::new(Orswot::new());
+        let add = set.inner().add(1, set.inner().read().derive_add_ctx("A"));
+        assert!(set.apply_once(add.clone()));
+        assert!(!set.apply_once(add.clone()));
+        assert_eq!(set.seen(), &VClock::from(Dot::new("A", 1)));
+
+        let rm = set.inner().rm(1, set.inner().contains(&1).derive_rm_ctx());
+        assert!(set.apply_once(rm.clone()));
+        assert!(set.apply_once(rm));
+
+        // the old add must not resurrect the member
+        assert!(!set.apply_once(add));
+        assert!(set.into_inner().read().val.is_empty());
+    }
+
+    #[test]
+    fn test_applied_ops_refuses_duplicates() {
+        let mut applied = AppliedOps::new();
+        assert!(applied.mark_and_check(Dot::new(1u8, 1)));
+        assert!(!applied.mark_and_check(Dot::new(1, 1)));
+        assert!(applied.mark_and_check(Dot::new(2, 1)));
+        assert!(applied.mark_and_check(Dot::new(1, 2)));
+        assert!(applied.is_applied(&Dot::new(1, 1)));
+        assert!(!applied.is_applied(&Dot::new(1, 3)));
+    }
+
+    #[test]
+    fn test_applied_ops_tracks_dots_past_a_gap() {
+        let mut applied = AppliedOps::new();
+        assert!(applied.mark_and_check(Dot::new(1u8, 3)));
+        assert!(!applied.is_applied(&Dot::new(1, 2)));
+        assert!(!applied.mark_and_check(Dot::new(1, 3)));
+        assert_eq!(applied.clock(), &VClock::new());
+
+        assert!(applied.mark_and_check(Dot::new(1, 2)));
+        assert!(applied.mark_and_check(Dot::new(1, 1)));
+        assert_eq!(applied.clock(), &VClock::from(Dot::new(1, 3)));
+        assert!(!applied.mark_and_check(Dot::new(1, 2)));
+        assert!(applied.mark_and_check(Dot::new(1, 4)));
+    }
+
+    #[test]
+    fn test_applied_ops_at_the_last_counter() {
+        let mut applied = AppliedOps::new();
+        applied.applied.apply(Dot::new(1u8, u64::MAX - 2));
+
+        assert!(applied.mark_and_check(Dot::new(1, u64::MAX)));
+        assert!(applied.mark_and_check(Dot::new(1, u64::MAX - 1)));
+        assert_eq!(applied.clock(), &VClock::from(Dot::new(1, u64::MAX)));
+        assert!(!applied.mark_and_check(Dot::new(1, u64::MAX)));
+    }
+}
@@ -27,6 +27,19 @@ impl<A> Dot<A> {
     }
 }
 
+impl<A: Ord> Dot<A> {
+    /// Total order on dots by actor, then counter.
+    ///
+    /// `Dot`'s `PartialOrd` is causal, dots of different actors are
+    /// incomparable. Use this order instead to sort dots deterministically,
+    /// e.g. for a canonical op log.
+    pub fn canonical_cmp(&self, other: &Self) -> Ordering {
+        self.actor
+            .cmp(&other.actor)
+            .then_with(|| self.counter.cmp(&other.counter))
+    }
+}
+
 impl<A: Clone> Dot<A> {
     /// Generate the successor of this dot
     pub fn inc(&self) -> Self {
@@ -120,6 +133,13 @@ mod test {
             dot.inc() == Dot::new(dot.actor, dot.counter + 1)
         }
 
+        fn canonical_cmp_agrees_with_partial_order(a: Dot<u8>, b: Dot<u8>) -> bool {
+            match a.partial_cmp(&b) {
+                Some(ordering) => a.canonical_cmp(&b) == ordering,
+                None => a.canonical_cmp(&b) == a.actor.cmp(&b.actor),
+            }
+        }
+
         fn test_partial_order(a: Dot<u8>, b: Dot<u8>) -> bool {
             let cmp_ab = a.partial_cmp(&b);
             let cmp_ba = b.partial_cmp(&a);
@@ -133,4 +153,24 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_canonical_sort() {
+        let mut dots = vec![
+            Dot::new("B", 1),
+            Dot::new("A", 2),
+            Dot::new("B", 0),
+            Dot::new("A", 1),
+        ];
+        dots.sort_by(Dot::canonical_cmp);
+        assert_eq!(
+            dots,
+            vec![
+                Dot::new("A", 1),
+                Dot::new("A", 2),
+                Dot::new("B", 0),
+                Dot::new("B", 1)
+            ]
+        );
+    }
 }
@@ -0,0 +1,63 @@
+use std::fmt::{self, Debug, Display};
+
+use crate::DotRange;
+
+/// The errors reported by the checked paths when ingesting malformed data,
+/// e.g. ops or state deserialized from an untrusted peer.
+///
+/// Only `Orswot` has `apply_checked` and `validate_state`, the counters'
+/// only checked path is `GCounter::checked_inc`: any dot is a well formed
+/// counter op and any set of counts a well formed counter, applying a
+/// stale or repeated op is a no-op rather than an error.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CrdtError<A> {
+    /// An op skips over some dots of its actor, the dots in the
+    /// range are missing.
+    DotGap(DotRange<A>),
+    /// The counter of this actor can't be incremented past `u64::MAX`.
+    Overflow {
+        /// The actor whose counter would overflow
+        actor: A,
+    },
+    /// The state references an actor its clock has never seen, or an op
+    /// references a replica past the end of a `FixedVClock`.
+    UnknownActor {
+        /// The actor missing from the clock
+        actor: A,
+    },
+    /// Reading an op stream failed with this I/O error.
+    Io(std::io::ErrorKind),
+    /// The op at this position of a stream could not be decoded.
+    MalformedOp {
+        /// The number of ops read before the malformed one
+        index: usize,
+    },
+    /// The op would grow a bounded set past its capacity.
+    CapacityExceeded {
+        /// The capacity of the set
+        capacity: usize,
+    },
+}
+
+impl<A> From<DotRange<A>> for CrdtError<A> {
+    fn from(range: DotRange<A>) -> Self {
+        CrdtError::DotGap(range)
+    }
+}
+
+impl<A: Debug> Display for CrdtError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrdtError::DotGap(range) => write!(f, "missing dots {}", range),
+            CrdtError::Overflow { actor } => write!(f, "counter of {:?} would overflow", actor),
+            CrdtError::UnknownActor { actor } => write!(f, "unknown actor {:?}", actor),
+            CrdtError::Io(kind) => write!(f, "reading op stream failed: {:?}", kind),
+            CrdtError::MalformedOp { index } => write!(f, "malformed op at index {}", index),
+            CrdtError::CapacityExceeded { capacity } => {
+                write!(f, "op would grow set past its capacity of {}", capacity)
+            }
+        }
+    }
+}
+
+impl<A: Debug> std::error::Error for CrdtError<A> {}
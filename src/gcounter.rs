@@ -1,11 +1,34 @@
 use num_bigint::BigUint;
+#[cfg(feature = "json")]
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::cmp;
+use std::collections::{btree_map, hash_map, hash_map::RandomState, BTreeMap, BTreeSet, HashMap};
+use std::fmt::{self, Debug, Display};
+use std::hash::{BuildHasher, Hash};
+#[cfg(feature = "json")]
+use std::io::Read;
+use std::iter;
+use std::marker::PhantomData;
 
-use crate::{traits::VacuousValidation, CmRDT, CvRDT, Dot, ResetRemove, VClock};
+use crate::causal::CausalOp;
+#[cfg(feature = "json")]
+use crate::codec;
+use crate::quickcheck::{Arbitrary, Gen};
+use crate::summary::CrdtSummary;
+#[cfg(feature = "json")]
+use crate::CanonicalBytes;
+use crate::{
+    traits::VacuousValidation, CmRDT, Compact, CrdtError, CvRDT, CvRDTIdentity, Dot, ResetRemove,
+    VClock,
+};
 
 /// `GCounter` is a grow-only witnessed counter.
 ///
+/// The per-actor counts are kept in a `CounterStore`, by default a `VClock`
+/// which iterates actors in order. See `HashGCounter` for a variant backed by
+/// a `HashMap` with a pluggable hasher.
+///
 /// # Examples
 ///
 /// ```
@@ -23,19 +46,135 @@ use crate::{traits::VacuousValidation, CmRDT, CvRDT, Dot, ResetRemove, VClock};
 /// assert!(a.read() > b.read());
 /// ```
 #[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
-pub struct GCounter<A: Ord> {
-    inner: VClock<A>,
+pub struct GCounter<A: Ord, S = VClock<A>> {
+    pub(crate) inner: S,
+    #[serde(skip)]
+    actor: PhantomData<A>,
+}
+
+/// A `GCounter` keeping its counts in a `HashMap` with the hasher `S`.
+///
+/// Merge and read semantics are identical to the default `GCounter`, only
+/// the iteration order over actors is unspecified.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{gcounter::HashGCounter, CmRDT};
+///
+/// let mut a: HashGCounter<&str> = Default::default();
+/// a.apply(a.inc("A"));
+/// assert_eq!(a.read(), 1u8.into());
+/// ```
+pub type HashGCounter<A, S = RandomState> = GCounter<A, HashMap<A, u64, S>>;
+
+/// Returned by `GCounter::merge_budgeted` and `Orswot::merge_budgeted`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeProgress {
+    /// Everything in the other state has been merged.
+    Complete,
+    /// The budget ran out, call again with the same state to resume.
+    Pending,
+}
+
+/// Storage for the per-actor counts of a `GCounter`.
+/// Actors missing from the store have an implied count of 0.
+pub trait CounterStore<A: Ord>: Default {
+    /// Return the count of this actor.
+    fn count(&self, actor: &A) -> u64;
+
+    /// Raise the actor's count to `dot.counter` if it is below it.
+    fn observe(&mut self, dot: Dot<A>);
+
+    /// Forget any actors whose count is not greater than their count in the given clock.
+    fn forget_dominated(&mut self, clock: &VClock<A>);
+
+    /// The iterator returned by `dots`.
+    type Dots<'a>: Iterator<Item = Dot<&'a A>>
+    where
+        Self: 'a,
+        A: 'a;
+
+    /// Returns an iterator over the stored counts as dots.
+    fn dots(&self) -> Self::Dots<'_>;
+}
+
+fn as_dot<'a, A>((actor, counter): (&'a A, &'a u64)) -> Dot<&'a A> {
+    Dot::new(actor, *counter)
+}
+
+impl<A: Ord> CounterStore<A> for VClock<A> {
+    type Dots<'a>
+        = iter::Map<btree_map::Iter<'a, A, u64>, fn((&'a A, &'a u64)) -> Dot<&'a A>>
+    where
+        A: 'a;
+
+    fn count(&self, actor: &A) -> u64 {
+        self.get(actor)
+    }
+
+    fn observe(&mut self, dot: Dot<A>) {
+        if self.get(&dot.actor) < dot.counter {
+            self.dots.insert(dot.actor, dot.counter);
+        }
+    }
+
+    fn forget_dominated(&mut self, clock: &VClock<A>) {
+        self.reset_remove(clock)
+    }
+
+    fn dots(&self) -> Self::Dots<'_> {
+        self.dots.iter().map(as_dot)
+    }
+}
+
+impl<A: Ord + Hash, S: BuildHasher + Default> CounterStore<A> for HashMap<A, u64, S> {
+    type Dots<'a>
+        = iter::Map<hash_map::Iter<'a, A, u64>, fn((&'a A, &'a u64)) -> Dot<&'a A>>
+    where
+        A: 'a,
+        S: 'a;
+
+    fn count(&self, actor: &A) -> u64 {
+        self.get(actor).cloned().unwrap_or(0)
+    }
+
+    fn observe(&mut self, dot: Dot<A>) {
+        if dot.counter == 0 {
+            // a zero count is implied for every actor, don't store it
+            return;
+        }
+        let counter = self.entry(dot.actor).or_insert(0);
+        if *counter < dot.counter {
+            *counter = dot.counter;
+        }
+    }
+
+    fn forget_dominated(&mut self, clock: &VClock<A>) {
+        self.retain(|actor, counter| *counter > clock.get(actor));
+    }
+
+    fn dots(&self) -> Self::Dots<'_> {
+        self.iter().map(as_dot)
+    }
 }
 
-impl<A: Ord> Default for GCounter<A> {
+impl<A: Ord, S: CounterStore<A>> Default for GCounter<A, S> {
     fn default() -> Self {
         Self {
             inner: Default::default(),
+            actor: PhantomData,
         }
     }
 }
 
-impl<A: Ord + Clone + Debug> CmRDT for GCounter<A> {
+impl<A: Ord + Clone, S: CounterStore<A>> Display for GCounter<A, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.read())
+    }
+}
+
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> CmRDT for GCounter<A, S> {
     type Op = Dot<A>;
     type Validation = VacuousValidation;
 
@@ -44,11 +183,11 @@ impl<A: Ord + Clone + Debug> CmRDT for GCounter<A> {
     }
 
     fn apply(&mut self, op: Self::Op) {
-        self.inner.apply(op)
+        self.observe(op)
     }
 }
 
-impl<A: Ord + Clone + Debug> CvRDT for GCounter<A> {
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> CvRDT for GCounter<A, S> {
     type Validation = VacuousValidation;
 
     fn validate_merge(&self, _other: &Self) -> Result<(), Self::Validation> {
@@ -56,13 +195,50 @@ impl<A: Ord + Clone + Debug> CvRDT for GCounter<A> {
     }
 
     fn merge(&mut self, other: Self) {
-        self.inner.merge(other.inner);
+        for dot in other.inner.dots() {
+            self.observe(Dot::new(dot.actor.clone(), dot.counter));
+        }
     }
 }
 
-impl<A: Ord> ResetRemove<A> for GCounter<A> {
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> CvRDTIdentity for GCounter<A, S> {
+    /// The empty counter.
+    fn identity() -> Self {
+        Default::default()
+    }
+}
+
+impl<A: Ord, S: CounterStore<A>> ResetRemove<A> for GCounter<A, S> {
     fn reset_remove(&mut self, clock: &VClock<A>) {
-        self.inner.reset_remove(&clock);
+        self.inner.forget_dominated(clock);
+    }
+}
+
+impl<A: Ord, S: CounterStore<A>> Compact<A> for GCounter<A, S> {
+    /// A `GCounter` holds no history, every count is part of its value.
+    fn compact(&mut self, _stable: &VClock<A>) {}
+}
+
+#[cfg(feature = "json")]
+impl<A: Ord + Clone + Serialize, S: CounterStore<A>> CanonicalBytes for GCounter<A, S> {
+    fn canonical_bytes(&self) -> Vec<u8> {
+        GCounter::canonical_bytes(self)
+    }
+}
+
+impl<A: Ord + Clone + Debug + Arbitrary> Arbitrary for GCounter<A> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        GCounter {
+            inner: VClock::arbitrary(g),
+            actor: PhantomData,
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.inner.shrink().map(|inner| GCounter {
+            inner,
+            actor: PhantomData,
+        }))
     }
 }
 
@@ -71,28 +247,492 @@ impl<A: Ord + Clone> GCounter<A> {
     pub fn new() -> Self {
         Default::default()
     }
+}
+
+impl<A: Ord + Clone, S: CounterStore<A>> GCounter<A, S> {
+    /// Returns a counter that has already applied every dot of `baseline`,
+    /// e.g. for a replica joining from a snapshot whose history was garbage
+    /// collected.
+    ///
+    /// The baseline counts are the counter's counts, so the value starts at
+    /// the sum of the baseline. Dots at or below the baseline are no-ops,
+    /// later dots apply as usual.
+    pub fn init_at(baseline: VClock<A>) -> Self {
+        let mut counter = Self::default();
+        for dot in baseline {
+            counter.inner.observe(dot);
+        }
+        counter
+    }
+
+    /// Returns the non-zero per-actor counts as dots, a compact form for
+    /// persisting the counter. Actors with a count of 0, e.g. left behind in
+    /// a `HashGCounter` by applying a zero dot, are omitted.
+    ///
+    /// The dots are in the store's iteration order.
+    pub fn to_sparse(&self) -> Vec<Dot<A>> {
+        self.inner
+            .dots()
+            .filter(|dot| dot.counter > 0)
+            .map(|dot| Dot::new(dot.actor.clone(), dot.counter))
+            .collect()
+    }
+
+    /// Returns a canonical encoding of the counter, the same as
+    /// `VClock::canonical_bytes` of its counts. Counters with the same counts
+    /// have the same canonical bytes, whatever their store's iteration order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an actor fails to serialize.
+    #[cfg(feature = "json")]
+    pub fn canonical_bytes(&self) -> Vec<u8>
+    where
+        A: Serialize,
+    {
+        serde_json::to_vec(&self.canonical_counts()).expect("failed to serialize actor")
+    }
+
+    /// The non-zero counts in actor order.
+    pub(crate) fn canonical_counts(&self) -> Vec<(&A, u64)> {
+        let mut counts: Vec<(&A, u64)> = self
+            .inner
+            .dots()
+            .filter(|dot| dot.counter > 0)
+            .map(|dot| (dot.actor, dot.counter))
+            .collect();
+        counts.sort_unstable();
+        counts
+    }
+
+    /// Rebuild a counter from the dots returned by `to_sparse`.
+    pub fn from_sparse(dots: Vec<Dot<A>>) -> Self {
+        let mut counter = Self::default();
+        for dot in dots {
+            counter.observe(dot);
+        }
+        counter
+    }
 
     /// Generate Op to increment the counter.
     pub fn inc(&self, actor: A) -> Dot<A> {
-        self.inner.inc(actor)
+        let counter = self.inner.count(&actor) + 1;
+        Dot::new(actor, counter)
+    }
+
+    /// Generate Op to increment the counter, tagged with the clock `deps` it
+    /// causally depends on, see `CausalOp`.
+    pub fn inc_with_deps(&self, actor: A, deps: VClock<A>) -> CausalOp<Dot<A>, A> {
+        CausalOp::new(self.inc(actor), deps)
+    }
+
+    /// Generate Op to increment the counter, or `CrdtError::Overflow` if this
+    /// actor's count is already at `u64::MAX` and can't be incremented any further.
+    pub fn checked_inc(&self, actor: A) -> Result<Dot<A>, CrdtError<A>> {
+        match self.inner.count(&actor).checked_add(1) {
+            Some(counter) => Ok(Dot::new(actor, counter)),
+            None => Err(CrdtError::Overflow { actor }),
+        }
     }
 
     /// Generate Op to increment the counter by a number of steps.
     pub fn inc_many(&self, actor: A, steps: u64) -> Dot<A> {
-        let steps = steps + self.inner.get(&actor);
+        let steps = steps + self.inner.count(&actor);
         Dot::new(actor, steps)
     }
 
+    /// Generate Op to increment the counter by `amount` in a single dot.
+    /// The actor's count saturates at `u64::MAX` rather than overflowing.
+    pub fn inc_by(&self, actor: A, amount: u64) -> Dot<A> {
+        let counter = self.inner.count(&actor).saturating_add(amount);
+        Dot::new(actor, counter)
+    }
+
+    /// Apply the op, returning true iff it raised the actor's count.
+    ///
+    /// Stale and redelivered ops return false, e.g. to skip re-broadcasting
+    /// them.
+    pub fn apply_detect(&mut self, op: &Dot<A>) -> bool {
+        if self.inner.count(&op.actor) >= op.counter {
+            return false;
+        }
+        self.observe(op.clone());
+        true
+    }
+
+    /// Apply a stream of dots, e.g. the observations of a gossip frame,
+    /// raising each actor's count to the max counter seen. Returns true iff
+    /// any count was raised.
+    pub fn observe_all(&mut self, dots: impl IntoIterator<Item = Dot<A>>) -> bool {
+        let mut changed = false;
+        for dot in dots {
+            if self.inner.count(&dot.actor) < dot.counter {
+                self.observe(dot);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Raise the actor's count to the dot's counter.
+    ///
+    /// In debug builds this asserts that the store kept the max of the old
+    /// count and the dot's counter, catching stores that lose or decrease
+    /// counts. Stale dots are fine, redelivered ops must be no-ops.
+    fn observe(&mut self, dot: Dot<A>) {
+        #[cfg(debug_assertions)]
+        let (actor, expected) = (
+            dot.actor.clone(),
+            std::cmp::max(self.inner.count(&dot.actor), dot.counter),
+        );
+
+        self.inner.observe(dot);
+
+        #[cfg(debug_assertions)]
+        assert_eq!(
+            self.inner.count(&actor),
+            expected,
+            "GCounter count must be the max of the observed counts"
+        );
+    }
+
+    /// Merge each of the given counters into this one.
+    ///
+    /// Merge is associative and commutative so the order doesn't matter.
+    pub fn merge_all(&mut self, others: &[Self]) {
+        for dot in others.iter().flat_map(|other| other.inner.dots()) {
+            self.observe(Dot::new(dot.actor.clone(), dot.counter));
+        }
+    }
+
+    /// Merge at most `max_entries` actors of `other` into this counter.
+    ///
+    /// Only actors where `other` is ahead of us count against the budget, so
+    /// calling this repeatedly with the same counter resumes where the last
+    /// call stopped, without keeping any state between calls. After a call
+    /// returns `MergeProgress::Complete`, self equals a full merge.
+    ///
+    /// Each call still scans `other` to find the actors left to merge, the
+    /// budget bounds the writes, not the reads.
+    pub fn merge_budgeted(&mut self, other: &Self, max_entries: usize) -> MergeProgress {
+        // take one more than the budget to learn whether we'll be done
+        let mut ahead: Vec<_> = other
+            .inner
+            .dots()
+            .filter(|dot| dot.counter > self.inner.count(dot.actor))
+            .take(max_entries.saturating_add(1))
+            .map(|dot| Dot::new(dot.actor.clone(), dot.counter))
+            .collect();
+        let progress = if ahead.len() > max_entries {
+            MergeProgress::Pending
+        } else {
+            MergeProgress::Complete
+        };
+
+        ahead.truncate(max_entries);
+        for dot in ahead {
+            self.observe(dot);
+        }
+        progress
+    }
+
+    /// Move the old actor's count to the new actor, e.g. after a replica
+    /// was given a new id. If the new actor already has a count, it keeps
+    /// the max of the two.
+    ///
+    /// The value is preserved, but this is not a CRDT operation: every
+    /// replica must rekey before merging with the others again, or the old
+    /// actor's count comes back on merge and is counted twice.
+    pub fn rekey_actor(&mut self, old: &A, new: A) {
+        let count = self.inner.count(old);
+        if count == 0 {
+            return;
+        }
+        let mut old_clock = VClock::new();
+        old_clock.dots.insert(old.clone(), count);
+        self.inner.forget_dominated(&old_clock);
+        self.observe(Dot::new(new, count));
+    }
+
     /// Return the current sum of this counter.
     pub fn read(&self) -> BigUint {
-        self.inner.iter().map(|dot| dot.counter).sum()
+        self.inner.dots().map(|dot| dot.counter).sum()
+    }
+
+    /// Returns the value together with the number of actors with a non-zero
+    /// count and the sum of their counts.
+    pub fn summary(&self) -> CrdtSummary<BigUint> {
+        let (actor_count, causal_length) = self
+            .inner
+            .dots()
+            .filter(|dot| dot.counter > 0)
+            .fold((0, 0u64), |(actors, length), dot| {
+                (actors + 1, length.saturating_add(dot.counter))
+            });
+        CrdtSummary {
+            value: self.read(),
+            actor_count,
+            causal_length,
+        }
+    }
+
+    /// Returns a counter holding only the actors where self is ahead of `base`.
+    ///
+    /// If self has seen everything `base` has, merging the difference into
+    /// `base` reconstructs self.
+    pub fn difference(&self, base: &Self) -> Self {
+        let mut diff = Self::default();
+        for dot in self.inner.dots() {
+            if dot.counter > base.inner.count(dot.actor) {
+                diff.inner.observe(Dot::new(dot.actor.clone(), dot.counter));
+            }
+        }
+        diff
+    }
+
+    /// Returns the part of this counter between the `low` and `high` clocks:
+    /// each actor's count capped at `high`, keeping only the actors where
+    /// that is still above `low`.
+    ///
+    /// Merging the interval into a replica that has seen `low` advances it
+    /// to `high`, or as far towards `high` as we have seen, so syncs can
+    /// ship bounded windows and resume from where the last one stopped.
+    pub fn delta_interval(&self, low: &VClock<A>, high: &VClock<A>) -> Self {
+        let mut interval = Self::default();
+        for dot in self.inner.dots() {
+            let counter = cmp::min(dot.counter, high.get(dot.actor));
+            if counter > low.get(dot.actor) {
+                interval.inner.observe(Dot::new(dot.actor.clone(), counter));
+            }
+        }
+        interval
+    }
+
+    /// Return the current sum of this counter as an `i128`.
+    ///
+    /// Cheaper than `read` and can't overflow for fewer than 2^63 actors.
+    pub fn value_i128(&self) -> i128 {
+        self.inner.dots().map(|dot| i128::from(dot.counter)).sum()
+    }
+
+    /// Returns the actor with the largest count, `None` for an empty counter.
+    ///
+    /// If several actors tie, which one is returned is unspecified.
+    pub fn max_actor(&self) -> Option<(&A, u64)> {
+        self.inner
+            .dots()
+            .max_by_key(|dot| dot.counter)
+            .map(|dot| (dot.actor, dot.counter))
+    }
+
+    /// Returns the actor with the smallest count, `None` for an empty counter.
+    ///
+    /// If several actors tie, which one is returned is unspecified.
+    pub fn min_actor(&self) -> Option<(&A, u64)> {
+        self.inner
+            .dots()
+            .min_by_key(|dot| dot.counter)
+            .map(|dot| (dot.actor, dot.counter))
+    }
+
+    /// Returns the `k` actors with the largest counts, largest first, and
+    /// the sum of the counts of all other actors, e.g. to export a counter
+    /// with many actors to monitoring.
+    ///
+    /// Ties are broken by actor order, so the result is deterministic.
+    /// Actors with a count of 0 are left out. The remainder saturates at
+    /// `u64::MAX`.
+    pub fn top_k(&self, k: usize) -> (Vec<(A, u64)>, u64) {
+        let mut counts: Vec<(&A, u64)> = self.canonical_counts();
+        let by_rank = |a: &(&A, u64), b: &(&A, u64)| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0));
+        if k < counts.len() {
+            counts.select_nth_unstable_by(k, by_rank);
+        }
+        let split = cmp::min(k, counts.len());
+        let remainder = counts[split..]
+            .iter()
+            .fold(0u64, |sum, (_, count)| sum.saturating_add(*count));
+        counts.truncate(split);
+        counts.sort_unstable_by(by_rank);
+        let top = counts
+            .into_iter()
+            .map(|(actor, count)| (actor.clone(), count))
+            .collect();
+        (top, remainder)
+    }
+
+    /// Returns the distribution of the per-actor counts.
+    ///
+    /// Counts are bucketed by powers of two, each key is the lower bound of
+    /// a bucket `[2^k, 2^(k+1))` and maps to the number of actors in it.
+    /// Actors with a count of 0 are left out.
+    pub fn histogram(&self) -> BTreeMap<u64, usize> {
+        let mut buckets = BTreeMap::new();
+        for dot in self.inner.dots().filter(|dot| dot.counter > 0) {
+            let bucket = 1 << (63 - dot.counter.leading_zeros());
+            *buckets.entry(bucket).or_insert(0) += 1;
+        }
+        buckets
+    }
+
+    /// Return the sum of the counts of just the given actors, saturating at
+    /// `u64::MAX`.
+    pub fn sum_group(&self, members: &BTreeSet<A>) -> u64 {
+        members.iter().fold(0u64, |sum, actor| {
+            sum.saturating_add(self.inner.count(actor))
+        })
+    }
+
+    /// Return the sum of the counts in each group of actors, where `classify`
+    /// maps an actor to its group. Groups saturate at `u64::MAX`.
+    pub fn partition_value<G: Ord>(&self, classify: impl Fn(&A) -> G) -> BTreeMap<G, u64> {
+        let mut groups = BTreeMap::new();
+        for dot in self.inner.dots() {
+            let total = groups.entry(classify(dot.actor)).or_insert(0u64);
+            *total = total.saturating_add(dot.counter);
+        }
+        groups
+    }
+}
+
+#[cfg(feature = "json")]
+impl<A: Ord + Clone + Debug + DeserializeOwned, S: CounterStore<A>> GCounter<A, S> {
+    /// Apply the ops framed by `codec::write_delimited` in `reader`, one at
+    /// a time, returning the number of ops applied.
+    ///
+    /// Only one op is held in memory at once, so arbitrarily long logs can
+    /// be replayed. If the stream is truncated or an op fails to decode the
+    /// ops read before it stay applied.
+    pub fn apply_stream<R: Read>(&mut self, reader: R) -> Result<usize, CrdtError<A>> {
+        codec::apply_stream(self, reader)
     }
 }
 
+/// Fold each run of consecutive ops of the same actor into a single op,
+/// e.g. to ship a buffer of single increments as one op.
+///
+/// A counter op carries the actor's new count rather than a step, so the
+/// largest op of a run already implies the others and applying the
+/// compacted ops has the same effect as applying all of them.
+pub fn compact_ops<A: PartialEq>(ops: Vec<Dot<A>>) -> Vec<Dot<A>> {
+    let mut compacted: Vec<Dot<A>> = Vec::with_capacity(ops.len());
+    for op in ops {
+        match compacted.last_mut() {
+            Some(last) if last.actor == op.actor => {
+                last.counter = cmp::max(last.counter, op.counter)
+            }
+            _ => compacted.push(op),
+        }
+    }
+    compacted
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    use quickcheck::quickcheck;
+    use std::hash::BuildHasher;
+
+    #[test]
+    fn test_delta_interval() {
+        let mut a = GCounter::new();
+        a.apply(a.inc_many("A", 10));
+        a.apply(a.inc_many("B", 4));
+        a.apply(a.inc_many("C", 2));
+
+        let low: VClock<_> = vec![Dot::new("A", 3), Dot::new("B", 4)]
+            .into_iter()
+            .collect();
+        let high: VClock<_> = vec![Dot::new("A", 6), Dot::new("B", 8), Dot::new("C", 1)]
+            .into_iter()
+            .collect();
+
+        let interval = a.delta_interval(&low, &high);
+        let expected: VClock<_> = vec![Dot::new("A", 6), Dot::new("C", 1)]
+            .into_iter()
+            .collect();
+        assert_eq!(interval.inner, expected);
+
+        // a replica at `low` reaches `high`, capped by what `a` has seen
+        let mut replica = GCounter::new();
+        replica.apply(Dot::new("A", 3));
+        replica.apply(Dot::new("B", 4));
+        replica.merge(interval);
+        let expected: VClock<_> = vec![Dot::new("A", 6), Dot::new("B", 4), Dot::new("C", 1)]
+            .into_iter()
+            .collect();
+        assert_eq!(replica.inner, expected);
+    }
+
+    #[test]
+    fn test_rekey_actor() {
+        let mut a = GCounter::new();
+        a.apply(a.inc_many("old-host", 5));
+        a.apply(a.inc_many("new-host", 2));
+        a.apply(a.inc("other"));
+
+        a.rekey_actor(&"old-host", "new-host");
+        assert_eq!(a.read(), 6u8.into());
+        assert_eq!(a.inner.count(&"old-host"), 0);
+        assert_eq!(a.inner.count(&"new-host"), 5);
+
+        // rekeying an unknown actor is a no-op
+        a.rekey_actor(&"old-host", "other");
+        assert_eq!(a.inner.count(&"other"), 1);
+    }
+
+    #[test]
+    fn test_merge_budgeted_in_steps() {
+        let mut big = GCounter::new();
+        for actor in 0..100u32 {
+            big.apply(big.inc_many(actor, u64::from(actor) + 1));
+        }
+        let mut a = GCounter::new();
+        a.apply(a.inc_many(7, 1_000));
+
+        let mut full = a.clone();
+        full.merge(big.clone());
+
+        let mut calls = 0;
+        while a.merge_budgeted(&big, 30) == MergeProgress::Pending {
+            calls += 1;
+            assert!(calls < 10);
+        }
+        // 99 actors are ahead of us, merged 30 at a time
+        assert_eq!(calls, 3);
+        assert_eq!(a, full);
+        assert_eq!(a.merge_budgeted(&big, 0), MergeProgress::Complete);
+    }
+
+    quickcheck! {
+        fn prop_identity_is_merge_neutral(dots: Vec<Dot<u8>>) -> bool {
+            let mut a = GCounter::new();
+            dots.into_iter().for_each(|dot| a.apply(dot));
+
+            let mut merged = a.clone();
+            merged.merge(GCounter::identity());
+            merged == a
+        }
+
+        fn prop_difference_reconstructs(base_dots: Vec<Dot<u8>>, more_dots: Vec<Dot<u8>>) -> bool {
+            let mut base = GCounter::new();
+            base_dots.into_iter().for_each(|dot| base.apply(dot));
+            let mut a = base.clone();
+            more_dots.into_iter().for_each(|dot| a.apply(dot));
+
+            let diff = a.difference(&base);
+            let no_stale_actors = diff
+                .inner
+                .iter()
+                .all(|dot| dot.counter > base.inner.get(dot.actor));
+
+            let mut merged = base.clone();
+            merged.merge(diff);
+            no_stale_actors && merged == a
+        }
+    }
+
     #[test]
     fn test_basic_by_one() {
         let mut a = GCounter::new();
@@ -124,4 +764,364 @@ mod test {
 
         assert_eq!(a.read(), b.read() + BigUint::from(steps));
     }
+
+    #[test]
+    fn test_value_i128_does_not_overflow() {
+        let mut a = GCounter::new();
+        a.apply(Dot::new("A", u64::MAX - 1));
+        a.apply(Dot::new("B", u64::MAX));
+        assert_eq!(a.value_i128(), 2 * i128::from(u64::MAX) - 1);
+        assert_eq!(a.read(), BigUint::from(a.value_i128() as u128));
+        assert_eq!(GCounter::<u8>::new().value_i128(), 0);
+    }
+
+    #[test]
+    fn test_merge_all_matches_sequential_merge() {
+        let mut x = GCounter::new();
+        x.apply(x.inc_many("A", 3));
+        let mut y = GCounter::new();
+        y.apply(y.inc_many("A", 1));
+        y.apply(y.inc("B"));
+        let mut z = GCounter::new();
+        z.apply(z.inc_many("C", 4));
+        let others = vec![x, y, z];
+
+        let mut a = GCounter::new();
+        a.apply(a.inc_many("B", 2));
+        let mut sequential = a.clone();
+        for other in others.iter() {
+            sequential.merge(other.clone());
+        }
+
+        a.merge_all(&others);
+        assert_eq!(a, sequential);
+        assert_eq!(a.read(), BigUint::from(9u8));
+    }
+
+    #[test]
+    fn test_min_and_max_actor() {
+        let mut a = GCounter::new();
+        assert_eq!(a.max_actor(), None);
+        assert_eq!(a.min_actor(), None);
+
+        a.apply(a.inc_many("A", 4));
+        a.apply(a.inc_many("B", 9));
+        a.apply(a.inc_many("C", 2));
+        assert_eq!(a.max_actor(), Some((&"B", 9)));
+        assert_eq!(a.min_actor(), Some((&"C", 2)));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut a = GCounter::new();
+        for (actor, count) in &[("A", 1), ("B", 2), ("C", 3), ("D", 1000), ("E", 1023)] {
+            a.apply(a.inc_many(*actor, *count));
+        }
+        a.apply(Dot::new("F", u64::MAX));
+
+        let expected: BTreeMap<u64, usize> = vec![(1, 1), (2, 2), (512, 2), (1 << 63, 1)]
+            .into_iter()
+            .collect();
+        assert_eq!(a.histogram(), expected);
+        assert!(GCounter::<u8>::new().histogram().is_empty());
+    }
+
+    #[test]
+    fn test_sum_group() {
+        let mut a = GCounter::new();
+        a.apply(a.inc_many("A", 3));
+        a.apply(a.inc_many("B", 5));
+        a.apply(a.inc_many("C", 7));
+
+        let group: BTreeSet<_> = vec!["A", "C", "D"].into_iter().collect();
+        assert_eq!(a.sum_group(&group), 10);
+        assert_eq!(a.sum_group(&BTreeSet::new()), 0);
+
+        a.apply(a.inc_by("D", u64::MAX));
+        assert_eq!(a.sum_group(&group), u64::MAX);
+    }
+
+    #[test]
+    fn test_init_at_baseline() {
+        let baseline: VClock<_> = vec![Dot::new("A", 4), Dot::new("B", 2)]
+            .into_iter()
+            .collect();
+        let mut a: GCounter<_> = GCounter::init_at(baseline.clone());
+        assert_eq!(a.read(), 6u8.into());
+
+        // history below the baseline is already applied
+        a.apply(Dot::new("A", 3));
+        assert_eq!(a.read(), 6u8.into());
+
+        // a delta from a replica at the baseline applies cleanly
+        let mut b: HashGCounter<_> = GCounter::init_at(baseline);
+        b.apply(b.inc("A"));
+        let mut delta = GCounter::new();
+        delta.apply(Dot::new("A", 5));
+        a.merge(delta);
+        assert_eq!(a.read(), b.read());
+        assert_eq!(a.read(), 7u8.into());
+    }
+
+    #[test]
+    fn test_partition_value() {
+        let mut a = GCounter::new();
+        a.apply(a.inc_many("eu-1", 3));
+        a.apply(a.inc_many("us-1", 5));
+        a.apply(a.inc_many("eu-2", 7));
+
+        let by_region = a.partition_value(|actor| actor.split('-').next().unwrap().to_string());
+        let expected: BTreeMap<_, _> = vec![("eu".to_string(), 10), ("us".to_string(), 5)]
+            .into_iter()
+            .collect();
+        assert_eq!(by_region, expected);
+        assert!(GCounter::<u8>::new().partition_value(|a| *a).is_empty());
+    }
+
+    #[derive(Default, Clone)]
+    struct FnvBuildHasher;
+
+    struct FnvHasher(u64);
+
+    impl std::hash::Hasher for FnvHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.0 ^= u64::from(*byte);
+                self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+    }
+
+    impl BuildHasher for FnvBuildHasher {
+        type Hasher = FnvHasher;
+
+        fn build_hasher(&self) -> FnvHasher {
+            FnvHasher(0xcbf2_9ce4_8422_2325)
+        }
+    }
+
+    #[test]
+    fn test_custom_hasher_matches_btree_backend() {
+        let mut ordered = GCounter::new();
+        let mut hashed: HashGCounter<u32, FnvBuildHasher> = Default::default();
+        let mut other_ordered = GCounter::new();
+        let mut other_hashed: HashGCounter<u32, FnvBuildHasher> = Default::default();
+
+        for i in 0..10_000u32 {
+            let actor = i % 97;
+            if i % 3 == 0 {
+                other_ordered.apply(other_ordered.inc(actor));
+                other_hashed.apply(other_hashed.inc(actor));
+            } else {
+                ordered.apply(ordered.inc_many(actor, u64::from(i % 5)));
+                hashed.apply(hashed.inc_many(actor, u64::from(i % 5)));
+            }
+        }
+
+        ordered.merge(other_ordered);
+        hashed.merge(other_hashed);
+        assert_eq!(ordered.read(), hashed.read());
+        for actor in 0..97 {
+            assert_eq!(ordered.inner.count(&actor), hashed.inner.count(&actor));
+        }
+
+        let clock: VClock<u32> = (0..50).map(|actor| Dot::new(actor, 200)).collect();
+        ordered.reset_remove(&clock);
+        hashed.reset_remove(&clock);
+        assert_eq!(ordered.read(), hashed.read());
+    }
+
+    #[test]
+    fn test_hash_store_ignores_zero_dots() {
+        let mut a: HashGCounter<u8> = Default::default();
+        a.apply(Dot::new(1, 0));
+        a.merge(a.clone());
+        assert!(a.inner.is_empty());
+        assert_eq!(a, Default::default());
+    }
+
+    /// A broken store that overwrites counts instead of keeping the max.
+    #[derive(Default)]
+    struct OverwritingStore(VClock<u8>);
+
+    impl CounterStore<u8> for OverwritingStore {
+        type Dots<'a> = <VClock<u8> as CounterStore<u8>>::Dots<'a>;
+
+        fn count(&self, actor: &u8) -> u64 {
+            self.0.get(actor)
+        }
+
+        fn observe(&mut self, dot: Dot<u8>) {
+            self.0.dots.insert(dot.actor, dot.counter);
+        }
+
+        fn forget_dominated(&mut self, clock: &VClock<u8>) {
+            self.0.reset_remove(clock)
+        }
+
+        fn dots(&self) -> Self::Dots<'_> {
+            self.0.dots()
+        }
+    }
+
+    #[test]
+    fn test_stale_op_is_a_noop() {
+        let mut a = GCounter::new();
+        a.apply(Dot::new(1u8, 5));
+        a.apply(Dot::new(1u8, 2));
+        a.merge_all(&[a.clone()]);
+        assert_eq!(a.read(), BigUint::from(5u8));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "must be the max of the observed counts")]
+    fn test_decreasing_count_trips_assertion() {
+        let mut a: GCounter<u8, OverwritingStore> = Default::default();
+        a.apply(Dot::new(1, 5));
+        a.apply(Dot::new(1, 2));
+    }
+
+    #[test]
+    fn test_checked_inc() {
+        let mut a = GCounter::new();
+        assert_eq!(a.checked_inc("A"), Ok(Dot::new("A", 1)));
+
+        a.apply(Dot::new("A", u64::MAX));
+        assert_eq!(a.checked_inc("A"), Err(CrdtError::Overflow { actor: "A" }));
+        assert_eq!(a.checked_inc("B"), Ok(Dot::new("B", 1)));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_apply_stream() {
+        let mut writer = GCounter::new();
+        let mut stream = Vec::new();
+        for actor in &[1u8, 2, 1, 3] {
+            let op = writer.inc(*actor);
+            codec::write_delimited(&mut stream, &op).unwrap();
+            writer.apply(op);
+        }
+
+        let mut a = GCounter::new();
+        assert_eq!(a.apply_stream(stream.as_slice()), Ok(4));
+        assert_eq!(a, writer);
+        assert_eq!(a.read(), 4u8.into());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_apply_stream_stops_at_truncated_frame() {
+        let mut stream = Vec::new();
+        codec::write_delimited(&mut stream, &Dot::new(1u8, 1)).unwrap();
+        codec::write_delimited(&mut stream, &Dot::new(1u8, 2)).unwrap();
+        stream.pop();
+
+        let mut a: GCounter<u8> = GCounter::new();
+        assert_eq!(
+            a.apply_stream(stream.as_slice()),
+            Err(CrdtError::Io(std::io::ErrorKind::UnexpectedEof))
+        );
+        assert_eq!(a.read(), 1u8.into());
+    }
+
+    #[test]
+    fn test_apply_detect() {
+        let mut a = GCounter::new();
+        let op = a.inc(1u8);
+        assert!(a.apply_detect(&op));
+        assert!(!a.apply_detect(&op));
+        assert!(!a.apply_detect(&Dot::new(1, 0)));
+        assert_eq!(a.read(), 1u8.into());
+    }
+
+    #[test]
+    fn test_sparse_round_trip_drops_zero_counts() {
+        let mut a: HashGCounter<u8> = Default::default();
+        a.apply(a.inc_many(1, 3));
+        a.apply(Dot::new(2, 0));
+
+        let sparse = a.to_sparse();
+        assert_eq!(sparse, vec![Dot::new(1, 3)]);
+
+        let b: HashGCounter<u8> = GCounter::from_sparse(sparse);
+        assert_eq!(b.inner.len(), 1);
+        assert_eq!(b.read(), a.read());
+        assert_eq!(b.inner.count(&1), 3);
+    }
+
+    #[test]
+    fn test_compact_ops() {
+        let mut a = GCounter::new();
+        let mut ops = Vec::new();
+        for _ in 0..5 {
+            let op = a.inc(1u8);
+            a.apply(op);
+            ops.push(op);
+        }
+        ops.push(a.inc(2));
+
+        let compacted = compact_ops(ops.clone());
+        assert_eq!(compacted, vec![Dot::new(1, 5), Dot::new(2, 1)]);
+
+        let mut b = GCounter::new();
+        compacted.into_iter().for_each(|op| b.apply(op));
+        let mut c = GCounter::new();
+        ops.into_iter().for_each(|op| c.apply(op));
+        assert_eq!(b, c);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_canonical_bytes_ignore_build_order() {
+        let mut a: HashGCounter<u16> = Default::default();
+        let mut b: HashGCounter<u16> = Default::default();
+        for actor in 0..50 {
+            a.apply(Dot::new(actor, 1));
+            b.apply(Dot::new(49 - actor, 1));
+        }
+        b.apply(Dot::new(100, 0));
+
+        assert_eq!(a.canonical_bytes(), b.canonical_bytes());
+        let vclock: GCounter<u16> = GCounter::from_sparse(a.to_sparse());
+        assert_eq!(vclock.canonical_bytes(), a.canonical_bytes());
+        assert_eq!(vclock.canonical_bytes(), vclock.inner.canonical_bytes());
+    }
+
+    #[test]
+    fn test_observe_all() {
+        let mut a = GCounter::new();
+        let frame = vec![
+            Dot::new(1u8, 3),
+            Dot::new(2, 1),
+            Dot::new(1, 5),
+            Dot::new(1, 2),
+        ];
+        assert!(a.observe_all(frame.clone()));
+        assert_eq!(a.inner.get(&1), 5);
+        assert_eq!(a.inner.get(&2), 1);
+
+        assert!(!a.observe_all(frame));
+        assert!(a.observe_all(vec![Dot::new(2, 1), Dot::new(3, 1)]));
+        assert_eq!(a.read(), 7u8.into());
+    }
+
+    #[test]
+    fn test_top_k() {
+        let mut a = GCounter::new();
+        for (actor, count) in [('a', 4), ('b', 9), ('c', 1), ('d', 4), ('e', 7)] {
+            a.apply(a.inc_many(actor, count));
+        }
+
+        assert_eq!(a.top_k(2), (vec![('b', 9), ('e', 7)], 9));
+        // ties go to the smaller actor
+        assert_eq!(a.top_k(3), (vec![('b', 9), ('e', 7), ('a', 4)], 5));
+        assert_eq!(a.top_k(0), (vec![], 25));
+        assert_eq!(a.top_k(10).0.len(), 5);
+        assert_eq!(a.top_k(10).1, 0);
+    }
 }
@@ -6,7 +6,7 @@ use crate::{traits::VacuousValidation, CmRDT, CvRDT};
 /// A `GSet` is a grow-only set.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct GSet<T: Ord> {
-    value: BTreeSet<T>,
+    pub(crate) value: BTreeSet<T>,
 }
 
 impl<T: Ord> Default for GSet<T> {
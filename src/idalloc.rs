@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+use crate::{traits::VacuousValidation, CmRDT, CvRDT, GSet};
+
+/// An `Id` is a cluster-unique identifier handed out by an `IdAllocator`.
+///
+/// Ids are ordered first by actor and then by sequence number.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Id<A> {
+    /// The actor that allocated this id
+    pub actor: A,
+    /// The position of this id in the actor's allocation sequence
+    pub seq: u64,
+}
+
+/// `IdAllocator` hands out `(actor, seq)` ids without coordination.
+///
+/// Every actor allocates from its own monotone sequence, so ids allocated by
+/// different replicas can never collide. The allocations themselves are kept
+/// in a `GSet`, merging two allocators is the union of their allocations.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{CmRDT, CvRDT, IdAllocator};
+///
+/// let mut a = IdAllocator::new();
+/// let mut b = IdAllocator::new();
+///
+/// let a_id = a.allocate("A");
+/// a.apply(a_id.clone());
+///
+/// let b_id = b.allocate("B");
+/// b.apply(b_id.clone());
+///
+/// a.merge(b);
+/// assert_ne!(a_id, b_id);
+/// assert!(a.contains(&a_id));
+/// assert!(a.contains(&b_id));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IdAllocator<A: Ord> {
+    ids: GSet<Id<A>>,
+}
+
+impl<A: Ord> Default for IdAllocator<A> {
+    fn default() -> Self {
+        Self { ids: GSet::new() }
+    }
+}
+
+impl<A: Ord> CvRDT for IdAllocator<A> {
+    type Validation = VacuousValidation;
+
+    fn validate_merge(&self, _other: &Self) -> Result<(), Self::Validation> {
+        Ok(())
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.ids.merge(other.ids)
+    }
+}
+
+impl<A: Ord> CmRDT for IdAllocator<A> {
+    type Op = Id<A>;
+    type Validation = VacuousValidation;
+
+    fn validate_op(&self, _op: &Self::Op) -> Result<(), Self::Validation> {
+        Ok(())
+    }
+
+    fn apply(&mut self, op: Self::Op) {
+        self.ids.apply(op)
+    }
+}
+
+impl<A: Ord + Clone> IdAllocator<A> {
+    /// Produce a new `IdAllocator`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Generate the next id for this actor.
+    ///
+    /// The id is only registered once it's applied, allocating twice without
+    /// applying in between will produce the same id.
+    pub fn allocate(&self, actor: A) -> Id<A> {
+        let seq = self.last_seq(&actor) + 1;
+        Id { actor, seq }
+    }
+
+    /// Returns `true` if this id has been allocated.
+    pub fn contains(&self, id: &Id<A>) -> bool {
+        self.ids.contains(id)
+    }
+
+    /// The sequence number of the last id allocated by this actor.
+    /// Actors that have not allocated anything have an implied seq of 0.
+    fn last_seq(&self, actor: &A) -> u64 {
+        let first = Id {
+            actor: actor.clone(),
+            seq: 0,
+        };
+        let last = Id {
+            actor: actor.clone(),
+            seq: u64::MAX,
+        };
+        self.ids
+            .value
+            .range(first..=last)
+            .next_back()
+            .map(|id| id.seq)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_allocate_is_monotone_per_actor() {
+        let mut a = IdAllocator::new();
+        for expected_seq in 1..5 {
+            let id = a.allocate("A");
+            assert_eq!(id.seq, expected_seq);
+            a.apply(id);
+        }
+        assert_eq!(a.allocate("B").seq, 1);
+    }
+
+    #[test]
+    fn test_merged_ids_are_distinct() {
+        let mut a = IdAllocator::new();
+        let mut b = IdAllocator::new();
+        let mut allocated = Vec::new();
+
+        for _ in 0..3 {
+            let id = a.allocate("A");
+            a.apply(id.clone());
+            allocated.push(id);
+
+            let id = b.allocate("B");
+            b.apply(id.clone());
+            allocated.push(id);
+        }
+
+        a.merge(b);
+
+        let distinct: BTreeSet<_> = allocated.iter().cloned().collect();
+        assert_eq!(distinct.len(), allocated.len());
+        assert!(allocated.iter().all(|id| a.contains(id)));
+        assert_eq!(a.allocate("B").seq, 4);
+    }
+}
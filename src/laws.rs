@@ -0,0 +1,101 @@
+/// Emits quickcheck tests checking that a CRDT obeys the CRDT laws.
+///
+/// `crdt_laws!(T)` checks that `merge` is commutative, associative and
+/// idempotent, `T` must implement `CvRDT`, `Arbitrary`, `Clone`, `PartialEq`
+/// and `Debug`. Merges that fail `validate_merge` are discarded.
+///
+/// `crdt_laws!(T, Op)` additionally checks that ops converge: applying a
+/// log of ops to a single replica gives the same state as splitting the log
+/// over two replicas and merging them. This requires `T: CmRDT<Op = Op> +
+/// Default` and ops that are independent of the other ops in the log.
+///
+/// The tests have fixed names, so invoke the macro once per module.
+///
+/// ```ignore
+/// mod gcounter_laws {
+///     use crdts::{crdt_laws, Dot, GCounter};
+///
+///     crdt_laws!(GCounter<u8>, Dot<u8>);
+/// }
+/// ```
+#[macro_export]
+macro_rules! crdt_laws {
+    ($crdt:ty) => {
+        #[test]
+        fn merge_is_commutative() {
+            fn prop(a: $crdt, b: $crdt) -> $crate::quickcheck::TestResult {
+                use $crate::CvRDT;
+                if a.validate_merge(&b).is_err() {
+                    return $crate::quickcheck::TestResult::discard();
+                }
+                let mut ab = a.clone();
+                ab.merge(b.clone());
+                let mut ba = b;
+                ba.merge(a);
+                $crate::quickcheck::TestResult::from_bool(ab == ba)
+            }
+            $crate::quickcheck::quickcheck(
+                prop as fn($crdt, $crdt) -> $crate::quickcheck::TestResult,
+            );
+        }
+
+        #[test]
+        fn merge_is_associative() {
+            fn prop(a: $crdt, b: $crdt, c: $crdt) -> $crate::quickcheck::TestResult {
+                use $crate::CvRDT;
+                if a.validate_merge(&b).is_err()
+                    || a.validate_merge(&c).is_err()
+                    || b.validate_merge(&c).is_err()
+                {
+                    return $crate::quickcheck::TestResult::discard();
+                }
+                let mut ab_c = a.clone();
+                ab_c.merge(b.clone());
+                ab_c.merge(c.clone());
+                let mut bc = b;
+                bc.merge(c);
+                let mut a_bc = a;
+                a_bc.merge(bc);
+                $crate::quickcheck::TestResult::from_bool(ab_c == a_bc)
+            }
+            $crate::quickcheck::quickcheck(
+                prop as fn($crdt, $crdt, $crdt) -> $crate::quickcheck::TestResult,
+            );
+        }
+
+        #[test]
+        fn merge_is_idempotent() {
+            fn prop(a: $crdt) -> bool {
+                use $crate::CvRDT;
+                let mut aa = a.clone();
+                aa.merge(a.clone());
+                aa == a
+            }
+            $crate::quickcheck::quickcheck(prop as fn($crdt) -> bool);
+        }
+    };
+    ($crdt:ty, $op:ty) => {
+        $crate::crdt_laws!($crdt);
+
+        #[test]
+        fn ops_converge() {
+            fn prop(ops: Vec<$op>) -> bool {
+                use $crate::{CmRDT, CvRDT};
+                let mut single = <$crdt>::default();
+                let mut left = <$crdt>::default();
+                let mut right = <$crdt>::default();
+                for (i, op) in ops.into_iter().enumerate() {
+                    single.apply(op.clone());
+                    if i % 2 == 0 {
+                        left.apply(op);
+                    } else {
+                        right.apply(op);
+                    }
+                }
+                left.merge(right);
+                left == single
+            }
+            $crate::quickcheck::quickcheck(prop as fn(Vec<$op>) -> bool);
+        }
+    };
+}
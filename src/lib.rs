@@ -11,11 +11,25 @@
 #![deny(missing_docs)]
 
 mod traits;
-pub use crate::traits::{Actor, CmRDT, CvRDT, ResetRemove};
+pub use crate::traits::{
+    Actor, CanonicalBytes, CmRDT, Compact, CvRDT, CvRDTIdentity, DynCvRDT, ResetRemove,
+};
+
+/// This module contains the error type of the checked paths.
+pub mod error;
+
+/// This module contains a typed wrapper for actor identifiers.
+pub mod actor;
+
+/// This module contains a typed wrapper keeping CRDT instances' ops apart.
+pub mod tagged;
 
 /// This module contains a Last-Write-Wins Register.
 pub mod lwwreg;
 
+/// This module contains pluggable marker sources for LWW writes.
+pub mod marker;
+
 /// This module contains a Multi-Value Register.
 pub mod mvreg;
 
@@ -28,15 +42,24 @@ pub mod dot;
 /// This module contains an Observed-Remove Set With Out Tombstones.
 pub mod orswot;
 
+/// This module contains an Observed-Remove Multiset.
+pub mod ormultiset;
+
 /// This module contains a Grow-only Counter.
 pub mod gcounter;
 
 /// This module contains a Grow-only Set.
 pub mod gset;
 
+/// This module contains a coordination-free unique Id Allocator.
+pub mod idalloc;
+
 /// This module contains a Positive-Negative Counter.
 pub mod pncounter;
 
+/// This module contains a rollup of sharded Positive-Negative Counters.
+pub mod aggregate;
+
 /// This module contains a Map with Reset-Remove and Observed-Remove semantics.
 pub mod map;
 
@@ -49,10 +72,49 @@ pub mod lseq;
 /// Version Vector with Exceptions
 pub mod vvwe;
 
+/// This module contains helpers for skipping redundant merges and ops.
+pub mod dedup;
+
+/// This module contains ops tagged with their causal dependencies.
+pub mod causal;
+
+/// This module contains replay verification of op logs.
+pub mod audit;
+
+/// This module contains a summary of a replica's value and causal progress.
+pub mod summary;
+
+/// This module contains a merge history recorder for debugging convergence.
+#[cfg(feature = "provenance")]
+pub mod provenance;
+
+/// This module contains convergence checkpoints for testing replicas.
+pub mod checkpoint;
+
+/// This module contains a runtime selectable serialization format.
+#[cfg(any(feature = "json", feature = "cbor", feature = "bincode"))]
+pub mod codec;
+
+/// This module contains a pluggable storage interface for persisting CRDT's.
+pub mod store;
+
+/// This module contains a Sequencer handing out monotone dots per actor.
+pub mod sequencer;
+
+/// This module contains a Replica binding a CRDT to its actor.
+pub mod replica;
+
+/// This module contains a thread-safe handle for sharing a CRDT.
+pub mod shared;
+
+#[cfg(feature = "testing")]
+mod laws;
+
 /// Top-level re-exports for CRDT structures.
 pub use crate::{
-    dot::Dot, dot::DotRange, gcounter::GCounter, gset::GSet, lwwreg::LWWReg, map::Map,
-    mvreg::MVReg, orswot::Orswot, pncounter::PNCounter, vclock::VClock,
+    dot::Dot, dot::DotRange, error::CrdtError, gcounter::GCounter, gset::GSet,
+    idalloc::IdAllocator, lwwreg::LWWReg, map::Map, mvreg::MVReg, orswot::Orswot,
+    pncounter::PNCounter, vclock::VClock,
 };
 
 /// A re-export of the quickcheck crate for use in property based testing of user code
@@ -154,7 +154,7 @@ impl<T: Clone, A: Ord + Clone> LSeq<T, A> {
             (prev, next)
         };
 
-        let ix_ident = self.gen.alloc(&lower_id, &upper_id);
+        let ix_ident = self.gen.alloc(lower_id, upper_id);
 
         assert!(lower_id < &ix_ident);
         assert!(&ix_ident < upper_id);
@@ -1,8 +1,9 @@
+use std::time::{Duration, SystemTime};
 use std::{error, fmt};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{CmRDT, CvRDT};
+use crate::{marker::MarkerSource, CmRDT, Compact, CvRDT, VClock};
 
 /// `LWWReg` is a simple CRDT that contains an arbitrary value
 /// along with an `Ord` that tracks causality. It is the responsibility
@@ -28,6 +29,11 @@ impl<V: Default, M: Default> Default for LWWReg<V, M> {
     }
 }
 
+impl<V, M, A: Ord> Compact<A> for LWWReg<V, M> {
+    /// An `LWWReg` holds no history, only the latest write.
+    fn compact(&mut self, _stable: &VClock<A>) {}
+}
+
 /// The Type of validation errors that may occur for an LWWReg.
 #[derive(Debug, PartialEq)]
 pub enum Validation {
@@ -113,6 +119,21 @@ impl<V: PartialEq, M: Ord> LWWReg<V, M> {
         }
     }
 
+    /// Updates value witnessed by a fresh marker from the given source.
+    ///
+    /// ```
+    /// use crdts::{marker::LamportMarker, LWWReg};
+    /// let mut source = LamportMarker::new("A");
+    /// let mut reg = LWWReg::default();
+    ///
+    /// reg.update_with(1, &mut source);
+    /// reg.update_with(2, &mut source);
+    /// assert_eq!(reg, LWWReg { val: 2, marker: (2, "A") });
+    /// ```
+    pub fn update_with<S: MarkerSource<Marker = M>>(&mut self, val: V, source: &mut S) {
+        self.update(val, source.now())
+    }
+
     /// An update is invalid if the marker is exactly the same as
     /// the current marker BUT the value is different:
     /// ```
@@ -131,6 +152,61 @@ impl<V: PartialEq, M: Ord> LWWReg<V, M> {
     }
 }
 
+impl<V: PartialEq, M: Ord> LWWReg<Option<V>, M> {
+    /// Clears the value witnessed by the given marker.
+    ///
+    /// A register holding an `Option` can represent deletion as `None`,
+    /// clearing is then an ordinary write: it wins over updates with smaller
+    /// markers and a later update with a larger marker revives the register.
+    ///
+    /// ```
+    /// use crdts::LWWReg;
+    /// let mut reg = LWWReg { val: Some("a"), marker: 1 };
+    ///
+    /// reg.clear(2);
+    /// assert_eq!(reg.val, None);
+    ///
+    /// // an update older than the clear stays cleared
+    /// reg.update(Some("b"), 1);
+    /// assert_eq!(reg.val, None);
+    ///
+    /// reg.update(Some("c"), 3);
+    /// assert_eq!(reg.val, Some("c"));
+    /// ```
+    pub fn clear(&mut self, marker: M) {
+        self.update(None, marker)
+    }
+}
+
+impl<V, A> LWWReg<V, (SystemTime, A)> {
+    /// Returns the value if it was written less than `ttl` before `now`,
+    /// going by the time of its marker, e.g. `SystemTimeMarker`'s.
+    ///
+    /// Expiry is computed from the replicated marker, so replicas agree on
+    /// whether the value expired given the same `now`. A marker after `now`
+    /// counts as just written.
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use crdts::LWWReg;
+    ///
+    /// let written = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+    /// let reg = LWWReg { val: "cached", marker: (written, "A") };
+    ///
+    /// let ttl = Duration::from_secs(10);
+    /// assert_eq!(reg.value_within(written + Duration::from_secs(5), ttl), Some(&"cached"));
+    /// assert_eq!(reg.value_within(written + Duration::from_secs(10), ttl), None);
+    /// ```
+    pub fn value_within(&self, now: SystemTime, ttl: Duration) -> Option<&V> {
+        let age = now.duration_since(self.marker.0).unwrap_or_default();
+        if age < ttl {
+            Some(&self.val)
+        } else {
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -176,6 +252,36 @@ mod test {
         assert_eq!(reg, LWWReg { val: 32, marker: 2 });
     }
 
+    #[test]
+    fn test_clear_converges_like_a_write() {
+        let mut a = LWWReg {
+            val: Some(1),
+            marker: 1,
+        };
+        let mut b = a.clone();
+
+        a.clear(2);
+        assert_eq!(a.val, None);
+        b.update(Some(2), 3);
+
+        let mut a_then_b = a.clone();
+        a_then_b.merge(b.clone());
+        let mut b_then_a = b.clone();
+        b_then_a.merge(a.clone());
+        assert_eq!(a_then_b, b_then_a);
+        assert_eq!(a_then_b.val, Some(2));
+
+        b.clear(4);
+        a.merge(b);
+        assert_eq!(
+            a,
+            LWWReg {
+                val: None,
+                marker: 4
+            }
+        );
+    }
+
     fn build_from_prim(prim: (u8, u16)) -> LWWReg<u8, (u16, u8)> {
         // we make the marker a tuple so that we avoid conflicts
         LWWReg {
@@ -241,4 +347,36 @@ mod test {
             r == r_snapshot
         }
     }
+
+    #[test]
+    fn test_value_within_expires_after_ttl() {
+        let written = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut reg = LWWReg {
+            val: 1,
+            marker: (written, 'A'),
+        };
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(reg.value_within(written, ttl), Some(&1));
+        assert_eq!(
+            reg.value_within(written + Duration::from_secs(59), ttl),
+            Some(&1)
+        );
+        assert_eq!(
+            reg.value_within(written + Duration::from_secs(61), ttl),
+            None
+        );
+        // a marker ahead of `now` hasn't expired
+        assert_eq!(
+            reg.value_within(written - Duration::from_secs(5), ttl),
+            Some(&1)
+        );
+
+        // an update refreshes the value
+        reg.update(2, (written + Duration::from_secs(100), 'B'));
+        assert_eq!(
+            reg.value_within(written + Duration::from_secs(120), ttl),
+            Some(&2)
+        );
+    }
 }
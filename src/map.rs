@@ -8,7 +8,11 @@ use std::mem;
 use serde::{Deserialize, Serialize};
 
 use crate::ctx::{AddCtx, ReadCtx, RmCtx};
-use crate::{CmRDT, CvRDT, Dot, ResetRemove, VClock};
+#[cfg(feature = "json")]
+use crate::vclock::CanonicalPairs;
+#[cfg(feature = "json")]
+use crate::CanonicalBytes;
+use crate::{CmRDT, Compact, CvRDT, Dot, ResetRemove, VClock};
 
 /// Val Trait alias to reduce redundancy in type decl.
 pub trait Val<A: Ord>: Clone + Default + ResetRemove<A> + CmRDT {}
@@ -92,8 +96,8 @@ impl<K: Ord, V: Val<A>, A: Ord + Hash> ResetRemove<A> for Map<K, V, A> {
         self.entries = mem::take(&mut self.entries)
             .into_iter()
             .filter_map(|(key, mut entry)| {
-                entry.clock.reset_remove(&clock);
-                entry.val.reset_remove(&clock);
+                entry.clock.reset_remove(clock);
+                entry.val.reset_remove(clock);
                 if entry.clock.is_empty() {
                     None // remove this entry since its been forgotten
                 } else {
@@ -105,7 +109,7 @@ impl<K: Ord, V: Val<A>, A: Ord + Hash> ResetRemove<A> for Map<K, V, A> {
         self.deferred = mem::take(&mut self.deferred)
             .into_iter()
             .filter_map(|(mut rm_clock, key)| {
-                rm_clock.reset_remove(&clock);
+                rm_clock.reset_remove(clock);
                 if rm_clock.is_empty() {
                     None // this deferred remove has been forgotten
                 } else {
@@ -114,7 +118,54 @@ impl<K: Ord, V: Val<A>, A: Ord + Hash> ResetRemove<A> for Map<K, V, A> {
             })
             .collect();
 
-        self.clock.reset_remove(&clock);
+        self.clock.reset_remove(clock);
+    }
+}
+
+impl<K: Ord, V: Val<A> + Compact<A>, A: Ord + Hash + Clone> Compact<A> for Map<K, V, A> {
+    /// Drops the history below `stable` from the deferred removes and
+    /// compacts every nested value.
+    ///
+    /// As with `Orswot::gc`, every actor whose dots we've already seen and
+    /// `stable` covers is dropped from the clock of a deferred remove, the
+    /// keys' dots were removed when the remove arrived. Removes left waiting
+    /// on the same clock collapse into one.
+    fn compact(&mut self, stable: &VClock<A>) {
+        let mut floor = self.clock.clone();
+        floor.glb(stable);
+
+        let mut deferred: HashMap<VClock<A>, BTreeSet<K>> = HashMap::new();
+        for (mut clock, mut keys) in mem::take(&mut self.deferred) {
+            clock.reset_remove(&floor);
+            deferred.entry(clock).or_default().append(&mut keys);
+        }
+        self.deferred = deferred;
+
+        for entry in self.entries.values_mut() {
+            entry.val.compact(stable);
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<K: Ord + Serialize, V: Val<A> + CanonicalBytes, A: Ord + Hash + Clone + Serialize>
+    CanonicalBytes for Map<K, V, A>
+{
+    fn canonical_bytes(&self) -> Vec<u8> {
+        Map::canonical_bytes(self)
+    }
+}
+
+impl<K: Ord + Display, V: Val<A> + Display, A: Ord + Hash> Display for Map<K, V, A> {
+    /// Renders the map as an indented tree, one entry per line.
+    /// Nested values that span multiple lines are indented under their key.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Map")?;
+        for (key, entry) in self.entries.iter() {
+            let val = entry.val.to_string().replace('\n', "\n  ");
+            write!(f, "\n  {} => {}", key, val)?;
+        }
+        Ok(())
     }
 }
 
@@ -171,14 +222,14 @@ impl<K: Ord, V: Val<A> + Debug, A: Ord + Hash + Clone + Debug> CmRDT for Map<K,
             Op::Rm { .. } => Ok(()),
             Op::Up { dot, key, op } => {
                 self.clock
-                    .validate_op(&dot)
+                    .validate_op(dot)
                     .map_err(CmRDTValidation::SourceOrder)?;
-                let entry = self.entries.get(&key).cloned().unwrap_or_default();
+                let entry = self.entries.get(key).cloned().unwrap_or_default();
                 entry
                     .clock
-                    .validate_op(&dot)
+                    .validate_op(dot)
                     .map_err(CmRDTValidation::SourceOrder)?;
-                entry.val.validate_op(&op).map_err(CmRDTValidation::Value)
+                entry.val.validate_op(op).map_err(CmRDTValidation::Value)
             }
         }
     }
@@ -187,7 +238,7 @@ impl<K: Ord, V: Val<A> + Debug, A: Ord + Hash + Clone + Debug> CmRDT for Map<K,
         match op {
             Op::Rm { clock, keyset } => self.apply_keyset_rm(keyset, clock),
             Op::Up { dot, key, op } => {
-                if self.clock.get(&dot.actor) >= dot.counter {
+                if self.clock.dominates_dot(&dot) {
                     // we've seen this op already
                     return;
                 }
@@ -213,7 +264,7 @@ impl<K: Ord + Clone + Debug, V: Val<A> + CvRDT + Debug, A: Ord + Hash + Clone +
         for (key, entry) in self.entries.iter() {
             for (other_key, other_entry) in other.entries.iter() {
                 for Dot { actor, counter } in entry.clock.iter() {
-                    if other_key != key && other_entry.clock.get(&actor) == counter {
+                    if other_key != key && other_entry.clock.get(actor) == counter {
                         return Err(CvRDTValidation::DoubleSpentDot {
                             dot: Dot::new(actor.clone(), counter),
                             our_key: key.clone(),
@@ -342,7 +393,7 @@ impl<K: Ord, V: Val<A>, A: Ord + Hash + Clone> Map<K, V, A> {
     /// Retrieve value stored under a key
     pub fn get(&self, key: &K) -> ReadCtx<Option<V>, A> {
         let add_clock = self.clock.clone();
-        let entry_opt = self.entries.get(&key);
+        let entry_opt = self.entries.get(key);
         ReadCtx {
             add_clock,
             rm_clock: entry_opt
@@ -368,7 +419,7 @@ impl<K: Ord, V: Val<A>, A: Ord + Hash + Clone> Map<K, V, A> {
         let key = key.into();
         let dot = ctx.dot.clone();
         let op = match self.entries.get(&key).map(|e| &e.val) {
-            Some(data) => f(&data, ctx),
+            Some(data) => f(data, ctx),
             None => f(&V::default(), ctx),
         };
 
@@ -398,6 +449,65 @@ impl<K: Ord, V: Val<A>, A: Ord + Hash + Clone> Map<K, V, A> {
         }
     }
 
+    /// Dump the map as a human readable tree of keys and the readings of
+    /// their values, useful when debugging convergence issues.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use crdts::{CmRDT, Map, PNCounter};
+    ///
+    /// let mut map: Map<&str, PNCounter<&str>, &str> = Map::new();
+    /// let add_ctx = map.read_ctx().derive_add_ctx("A");
+    /// map.apply(map.update("likes", add_ctx, |c, a| c.inc(a.dot.actor)));
+    ///
+    /// assert_eq!(map.dump(), "Map\n  likes => 1");
+    /// ```
+    pub fn dump(&self) -> String
+    where
+        K: Display,
+        V: Display,
+    {
+        self.to_string()
+    }
+
+    /// Returns a canonical encoding of the map: the JSON triple of the
+    /// canonical pairs of its clock, its entries in key order with their
+    /// clocks and the canonical bytes of their values, and its deferred
+    /// removes. Equal maps have the same canonical bytes, whatever the
+    /// iteration order of their hash maps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a key or actor fails to serialize.
+    #[cfg(feature = "json")]
+    pub fn canonical_bytes(&self) -> Vec<u8>
+    where
+        K: Serialize,
+        V: CanonicalBytes,
+        A: Serialize,
+    {
+        let entries: Vec<(&K, CanonicalPairs<A>, Vec<u8>)> = self
+            .entries
+            .iter()
+            .map(|(key, entry)| {
+                (
+                    key,
+                    entry.clock.canonical_pairs(),
+                    entry.val.canonical_bytes(),
+                )
+            })
+            .collect();
+        let mut deferred: Vec<(CanonicalPairs<A>, &BTreeSet<K>)> = self
+            .deferred
+            .iter()
+            .map(|(clock, keys)| (clock.canonical_pairs(), keys))
+            .collect();
+        deferred.sort_unstable();
+        serde_json::to_vec(&(self.clock.canonical_pairs(), entries, deferred))
+            .expect("failed to serialize key")
+    }
+
     /// apply the pending deferred removes
     fn apply_deferred(&mut self) {
         let deferred = mem::take(&mut self.deferred);
@@ -409,12 +519,12 @@ impl<K: Ord, V: Val<A>, A: Ord + Hash + Clone> Map<K, V, A> {
     /// Apply a set of key removals given a clock.
     fn apply_keyset_rm(&mut self, mut keyset: BTreeSet<K>, clock: VClock<A>) {
         for key in keyset.iter() {
-            if let Some(entry) = self.entries.get_mut(&key) {
+            if let Some(entry) = self.entries.get_mut(key) {
                 entry.clock.reset_remove(&clock);
                 if entry.clock.is_empty() {
                     // The entry clock says we have no info on this entry.
                     // So remove the entry
-                    self.entries.remove(&key);
+                    self.entries.remove(key);
                 } else {
                     // The entry clock is not empty so this means we still
                     // have some information on this entry, keep it.
@@ -673,6 +783,9 @@ mod test {
                         .into_iter()
                         .collect(),
                         deferred: HashMap::new(),
+                        capacity: None,
+                        subscribers: Default::default(),
+                        removals: Default::default(),
                     },
                 },
             )]
@@ -696,7 +809,10 @@ mod test {
                             entries: vec![(2, VClock::from(Dot::new(93, 1)))]
                                 .into_iter()
                                 .collect(),
-                            deferred: HashMap::new()
+                            deferred: HashMap::new(),
+                            capacity: None,
+                            subscribers: Default::default(),
+                            removals: Default::default(),
                         }
                     }
                 )]
@@ -710,4 +826,45 @@ mod test {
 
         assert_eq!(m1, m2);
     }
+
+    #[test]
+    fn test_compact_reclaims_deferred_history() {
+        use crate::PNCounter;
+
+        let mut m: Map<u8, PNCounter<u8>, u8> = Map::new();
+        for key in 0..3 {
+            let ctx = m.read_ctx().derive_add_ctx(1);
+            m.apply(m.update(key, ctx, |c, a| c.inc(a.dot.actor)));
+        }
+
+        // removes that also witnessed an add from 2 we haven't seen are deferred
+        for key in 0..3 {
+            let mut clock = m.read_ctx().rm_clock;
+            clock.apply(Dot::new(2, 1));
+            m.apply(Op::Rm {
+                clock,
+                keyset: vec![key, key + 10].into_iter().collect(),
+            });
+            let ctx = m.read_ctx().derive_add_ctx(1);
+            m.apply(m.update(key + 20, ctx, |c, a| c.inc(a.dot.actor)));
+        }
+        assert_eq!(m.deferred.len(), 3);
+        let before = m.clone();
+
+        m.compact(&Dot::new(1, 6).into());
+        assert_eq!(m.deferred.len(), 1);
+        assert_eq!(m.deferred.keys().next(), Some(&Dot::new(2, 1).into()));
+        assert_eq!(m.len().val, before.len().val);
+
+        let late = Op::Up {
+            dot: Dot::new(2, 1),
+            key: 12,
+            op: PNCounter::new().inc(2),
+        };
+        let mut before = before;
+        before.apply(late.clone());
+        m.apply(late);
+        assert_eq!(m.get(&12).val, None);
+        assert_eq!(m, before);
+    }
 }
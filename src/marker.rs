@@ -0,0 +1,300 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// A `Marker` orders the writes to an LWW register.
+///
+/// Any `Ord + Clone` type is a marker, it's up to the `MarkerSource` to hand
+/// out markers that grow monotonically and are globally unique.
+pub trait Marker: Ord + Clone {}
+
+impl<M: Ord + Clone> Marker for M {}
+
+/// A `MarkerSource` hands out markers for LWW writes, decoupling the register
+/// from whichever clock backs it.
+pub trait MarkerSource {
+    /// The marker handed out by this source.
+    type Marker: Marker;
+
+    /// Returns a marker for a write happening now.
+    fn now(&mut self) -> Self::Marker;
+}
+
+/// `SystemTimeMarker` hands out wall clock markers, tie broken by actor so
+/// that writes from different actors in the same instant don't conflict.
+///
+/// Markers from a single source never go backwards, even if the wall clock
+/// does. Wall clocks of different replicas drift apart, so a write with an
+/// older marker may win over a write that happened later. Don't use this
+/// unless you are comfortable with that.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SystemTimeMarker<A> {
+    actor: A,
+    last: Option<SystemTime>,
+}
+
+impl<A> SystemTimeMarker<A> {
+    /// Returns a new wall clock source for this actor.
+    pub fn new(actor: A) -> Self {
+        Self { actor, last: None }
+    }
+}
+
+impl<A: Ord + Clone> MarkerSource for SystemTimeMarker<A> {
+    type Marker = (SystemTime, A);
+
+    fn now(&mut self) -> Self::Marker {
+        let time = match self.last {
+            Some(last) => std::cmp::max(last, SystemTime::now()),
+            None => SystemTime::now(),
+        };
+        self.last = Some(time);
+        (time, self.actor.clone())
+    }
+}
+
+/// `LamportMarker` hands out Lamport timestamps, tie broken by actor.
+///
+/// Feed it the markers of remote writes with `observe` so that local writes
+/// are ordered after every write this replica has seen.
+///
+/// ```
+/// use crdts::marker::{LamportMarker, MarkerSource};
+///
+/// let mut source = LamportMarker::new("A");
+/// assert_eq!(source.now(), (1, "A"));
+///
+/// source.observe(&(5, "B"));
+/// assert_eq!(source.now(), (6, "A"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LamportMarker<A> {
+    actor: A,
+    counter: u64,
+}
+
+impl<A> LamportMarker<A> {
+    /// Returns a new Lamport source for this actor.
+    pub fn new(actor: A) -> Self {
+        Self { actor, counter: 0 }
+    }
+
+    /// Make sure the markers handed out from now on are past this marker.
+    pub fn observe(&mut self, marker: &(u64, A)) {
+        self.counter = std::cmp::max(self.counter, marker.0);
+    }
+}
+
+impl<A: Ord + Clone> MarkerSource for LamportMarker<A> {
+    type Marker = (u64, A);
+
+    fn now(&mut self) -> Self::Marker {
+        self.counter = self.counter.saturating_add(1);
+        (self.counter, self.actor.clone())
+    }
+}
+
+/// `Hlc` is a hybrid logical clock: the wall clock in milliseconds paired
+/// with a logical counter ordering events within the same millisecond.
+///
+/// The timestamps from `tick` and `update` never go backwards, even when
+/// the wall clock does or a remote clock runs ahead of ours. While the wall
+/// clock is behind the clock's physical time, the logical counter advances
+/// instead.
+///
+/// `Hlc` is ordered by physical time, then by logical counter. Timestamps of
+/// different replicas may be equal, pair them with the actor when they have
+/// to be globally unique.
+///
+/// ```
+/// use crdts::marker::Hlc;
+///
+/// let mut clock = Hlc::default();
+/// let a = clock.tick_at(10);
+/// // the wall clock jumped backwards
+/// let b = clock.tick_at(5);
+/// assert!(b > a);
+/// assert_eq!((b.physical(), b.logical()), (10, 1));
+/// ```
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Hlc {
+    physical: u64,
+    logical: u64,
+}
+
+impl Hlc {
+    /// Returns the physical component, in milliseconds since the unix epoch.
+    pub fn physical(&self) -> u64 {
+        self.physical
+    }
+
+    /// Returns the logical component.
+    pub fn logical(&self) -> u64 {
+        self.logical
+    }
+
+    /// Advance the clock for a local event and return its timestamp.
+    pub fn tick(&mut self) -> Hlc {
+        self.tick_at(wall_clock_ms())
+    }
+
+    /// Advance the clock for a local event happening at the given wall clock
+    /// time, in milliseconds since the unix epoch, and return its timestamp.
+    pub fn tick_at(&mut self, now: u64) -> Hlc {
+        if now > self.physical {
+            self.physical = now;
+            self.logical = 0;
+        } else {
+            self.logical = self.logical.saturating_add(1);
+        }
+        *self
+    }
+
+    /// Advance the clock past a received timestamp and return the timestamp
+    /// of the receive event.
+    pub fn update(&mut self, remote: &Hlc) -> Hlc {
+        self.update_at(remote, wall_clock_ms())
+    }
+
+    /// Advance the clock past a timestamp received at the given wall clock
+    /// time, in milliseconds since the unix epoch, and return the timestamp
+    /// of the receive event.
+    ///
+    /// A remote timestamp ahead of our wall clock is adopted, then the
+    /// logical counter carries the clock until the wall clock catches up.
+    pub fn update_at(&mut self, remote: &Hlc, now: u64) -> Hlc {
+        let physical = self.physical.max(remote.physical).max(now);
+        let logical = if physical == self.physical && physical == remote.physical {
+            self.logical.max(remote.logical).saturating_add(1)
+        } else if physical == self.physical {
+            self.logical.saturating_add(1)
+        } else if physical == remote.physical {
+            remote.logical.saturating_add(1)
+        } else {
+            0
+        };
+        self.physical = physical;
+        self.logical = logical;
+        *self
+    }
+}
+
+impl MarkerSource for Hlc {
+    type Marker = Hlc;
+
+    fn now(&mut self) -> Self::Marker {
+        self.tick()
+    }
+}
+
+fn wall_clock_ms() -> u64 {
+    // a wall clock before the epoch is treated as the epoch, the logical
+    // counter keeps the timestamps increasing until it recovers
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{CvRDT, LWWReg};
+
+    #[test]
+    fn test_lamport_markers_are_monotone() {
+        let mut source = LamportMarker::new('A');
+        let mut prev = source.now();
+        for remote in &[(3, 'B'), (1, 'C'), (10, 'B')] {
+            source.observe(remote);
+            let marker = source.now();
+            assert!(marker > prev);
+            assert!(&marker > remote);
+            prev = marker;
+        }
+    }
+
+    #[test]
+    fn test_lamport_markers_resolve_lww() {
+        let mut a_source = LamportMarker::new('A');
+        let mut b_source = LamportMarker::new('B');
+
+        let mut a = LWWReg::default();
+        a.update_with("a1", &mut a_source);
+        let mut b = a.clone();
+        b_source.observe(&b.marker);
+
+        // b saw a's write, so its next write wins
+        b.update_with("b1", &mut b_source);
+        a.merge(b.clone());
+        assert_eq!(a.val, "b1");
+
+        // concurrent writes with the same counter are tie broken by actor
+        a_source.observe(&a.marker);
+        a.update_with("a2", &mut a_source);
+        b.update_with("b2", &mut b_source);
+        assert_eq!(a.marker.0, b.marker.0);
+
+        let mut a_then_b = a.clone();
+        a_then_b.merge(b.clone());
+        b.merge(a);
+        assert_eq!(a_then_b, b);
+        assert_eq!(b.val, "b2");
+    }
+
+    #[test]
+    fn test_hlc_is_monotone_under_skew() {
+        let mut clock = Hlc::default();
+        let mut prev = clock.tick_at(100);
+
+        // wall clock jumps backwards, then stalls, then recovers
+        for now in &[40, 40, 99, 100, 101] {
+            let ts = clock.tick_at(*now);
+            assert!(ts > prev);
+            prev = ts;
+        }
+        assert_eq!((prev.physical(), prev.logical()), (101, 0));
+    }
+
+    #[test]
+    fn test_hlc_update_with_far_future_remote() {
+        let mut clock = Hlc::default();
+        clock.tick_at(100);
+
+        let mut remote = Hlc::default();
+        let future = remote.tick_at(1_000_000);
+
+        let received = clock.update_at(&future, 101);
+        assert!(received > future);
+        assert_eq!((received.physical(), received.logical()), (1_000_000, 1));
+
+        // local ticks stay ahead of the remote until the wall clock catches up
+        let next = clock.tick_at(102);
+        assert!(next > received);
+        assert_eq!((next.physical(), next.logical()), (1_000_000, 2));
+        assert_eq!(clock.tick_at(1_000_001).logical(), 0);
+    }
+
+    #[test]
+    fn test_hlc_update_is_monotone() {
+        let mut a = Hlc::default();
+        let mut b = Hlc::default();
+        let sent = a.tick_at(50);
+        b.tick_at(50);
+        b.tick_at(50);
+
+        let received = b.update_at(&sent, 20);
+        assert!(received > sent);
+        assert_eq!((received.physical(), received.logical()), (50, 2));
+    }
+
+    #[test]
+    fn test_system_time_markers_are_monotone() {
+        let mut source = SystemTimeMarker::new('A');
+        let first = source.now();
+        assert!(source.now() >= first);
+    }
+}
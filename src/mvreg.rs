@@ -5,7 +5,7 @@ use std::mem;
 use serde::{Deserialize, Serialize};
 
 use crate::ctx::{AddCtx, ReadCtx};
-use crate::{traits::VacuousValidation, CmRDT, CvRDT, ResetRemove, VClock};
+use crate::{traits::VacuousValidation, CmRDT, Compact, CvRDT, ResetRemove, VClock};
 
 /// MVReg (Multi-Value Register)
 /// On concurrent writes, we will keep all values for which
@@ -31,6 +31,8 @@ use crate::{traits::VacuousValidation, CmRDT, CvRDT, ResetRemove, VClock};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MVReg<V, A: Ord> {
     vals: Vec<(VClock<A>, V)>,
+    #[serde(skip)]
+    max_values: Option<usize>,
 }
 
 /// Defines the set of operations over the MVReg
@@ -89,7 +91,7 @@ impl<V, A: Ord> ResetRemove<A> for MVReg<V, A> {
         self.vals = mem::take(&mut self.vals)
             .into_iter()
             .filter_map(|(mut val_clock, val)| {
-                val_clock.reset_remove(&clock);
+                val_clock.reset_remove(clock);
                 if val_clock.is_empty() {
                     None // remove this value from the register
                 } else {
@@ -100,12 +102,69 @@ impl<V, A: Ord> ResetRemove<A> for MVReg<V, A> {
     }
 }
 
+impl<V, A: Ord> Compact<A> for MVReg<V, A> {
+    /// An `MVReg` holds no history, every clock orders a live value.
+    fn compact(&mut self, _stable: &VClock<A>) {}
+}
+
 impl<V, A: Ord> Default for MVReg<V, A> {
     fn default() -> Self {
-        Self { vals: Vec::new() }
+        Self {
+            vals: Vec::new(),
+            max_values: None,
+        }
+    }
+}
+
+impl<V, A: Ord> MVReg<V, A> {
+    /// Construct a new empty MVReg retaining at most `max_values`
+    /// concurrent values.
+    ///
+    /// When the concurrent values outgrow the bound, the lowest ranked ones
+    /// are dropped. Values rank by the `causal_length` of their clock, ties
+    /// broken by comparing the clocks actor by actor, so replicas agree on
+    /// which values to drop.
+    ///
+    /// This is a bounded approximation of an MVReg, not a strict one. A
+    /// dropped value's history is forgotten, so merging with a replica
+    /// that still holds it can bring it back until it's outranked again,
+    /// and replicas only converge to the same bounded set once they have
+    /// seen the same writes. The bound is local configuration, it is not
+    /// serialized and should be the same on every replica.
+    pub fn with_max_values(max_values: usize) -> Self {
+        Self {
+            vals: Vec::new(),
+            max_values: Some(max_values),
+        }
+    }
+
+    fn truncate_frontier(&mut self) {
+        let max_values = match self.max_values {
+            Some(max_values) => max_values,
+            None => return,
+        };
+        while self.vals.len() > max_values {
+            let lowest = self
+                .vals
+                .iter()
+                .enumerate()
+                .min_by(|(_, (a, _)), (_, (b, _))| rank(a, b))
+                .map(|(i, _)| i);
+            match lowest {
+                Some(i) => self.vals.remove(i),
+                None => return,
+            };
+        }
     }
 }
 
+fn rank<A: Ord>(a: &VClock<A>, b: &VClock<A>) -> Ordering {
+    a.causal_length().cmp(&b.causal_length()).then_with(|| {
+        let a_dots = a.iter().map(|dot| (dot.actor, dot.counter));
+        a_dots.cmp(b.iter().map(|dot| (dot.actor, dot.counter)))
+    })
+}
+
 impl<V, A: Ord> CvRDT for MVReg<V, A> {
     type Validation = VacuousValidation;
 
@@ -127,6 +186,7 @@ impl<V, A: Ord> CvRDT for MVReg<V, A> {
                 .filter(|(clock, _)| self.vals.iter().all(|(c, _)| clock != c))
                 .collect::<Vec<_>>(),
         );
+        self.truncate_frontier();
     }
 }
 
@@ -168,6 +228,7 @@ impl<V, A: Ord> CmRDT for MVReg<V, A> {
 
                 if should_add {
                     self.vals.push((clock, val));
+                    self.truncate_frontier();
                 }
             }
         }
@@ -203,6 +264,36 @@ impl<V, A: Ord + Clone + fmt::Debug> MVReg<V, A> {
         }
     }
 
+    /// Pick a single value out of the concurrent values with `resolver`,
+    /// or `None` if the register was never written.
+    ///
+    /// The register keeps all concurrent values, the resolver only shapes
+    /// the view. Make it deterministic, e.g. pick the max, so replicas with
+    /// the same values resolve to the same one regardless of their order.
+    ///
+    /// ```rust
+    /// use crdts::{CmRDT, MVReg};
+    /// let mut reg = MVReg::new();
+    /// let bob = reg.write("bob", reg.read_ctx().derive_add_ctx("A"));
+    /// let alice = reg.write("alice", reg.read_ctx().derive_add_ctx("B"));
+    /// reg.apply(bob);
+    /// reg.apply(alice);
+    ///
+    /// let longest = reg.resolve(|vals| vals.iter().max_by_key(|v| v.len()).copied().unwrap());
+    /// assert_eq!(longest, Some(&"alice"));
+    /// ```
+    pub fn resolve<F>(&self, resolver: F) -> Option<&V>
+    where
+        F: for<'v> Fn(&[&'v V]) -> &'v V,
+    {
+        let vals: Vec<&V> = self.vals.iter().map(|(_, v)| v).collect();
+        if vals.is_empty() {
+            None
+        } else {
+            Some(resolver(&vals))
+        }
+    }
+
     /// Retrieve the current read context
     pub fn read_ctx(&self) -> ReadCtx<(), A> {
         let clock = self.clock();
@@ -0,0 +1,278 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::mem;
+
+use serde::{Deserialize, Serialize};
+
+use crate::traits::VacuousValidation;
+use crate::{Actor, CmRDT, CvRDT, Dot, DotRange, VClock};
+
+/// `ORMultiset` is an observed-remove multiset: an element can be added
+/// many times and each add is a distinct instance witnessed by its own dot,
+/// a remove takes away one instance its replica has observed.
+///
+/// As with the `Orswot`, adds win over concurrent removes. Two replicas
+/// concurrently removing an element without having seen each other pick
+/// the same instance, so together they only lower its count by one.
+///
+/// ```
+/// use crdts::ormultiset::ORMultiset;
+/// use crdts::{CmRDT, CvRDT};
+///
+/// let mut a = ORMultiset::new();
+/// a.apply(a.add("apple", 'A'));
+/// a.apply(a.add("apple", 'A'));
+///
+/// let mut b = a.clone();
+/// b.apply(b.remove(&"apple").unwrap());
+///
+/// a.merge(b);
+/// assert_eq!(a.count(&"apple"), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ORMultiset<T: Ord, A: Ord> {
+    clock: VClock<A>,
+    entries: BTreeMap<T, BTreeSet<(A, u64)>>,
+    deferred: BTreeMap<T, BTreeSet<(A, u64)>>,
+}
+
+/// Ops on an `ORMultiset`, each adds or removes a single instance.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Op<T, A> {
+    /// Add an instance of the member
+    Add {
+        /// witnessing dot of the new instance
+        dot: Dot<A>,
+        /// Member to add
+        member: T,
+    },
+    /// Remove an instance of the member
+    Rm {
+        /// witnessing dot of the removed instance
+        dot: Dot<A>,
+        /// Member to remove
+        member: T,
+    },
+}
+
+impl<T: Ord, A: Ord> Default for ORMultiset<T, A> {
+    fn default() -> Self {
+        Self {
+            clock: VClock::new(),
+            entries: BTreeMap::new(),
+            deferred: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Ord + Clone, A: Actor + Debug> CmRDT for ORMultiset<T, A> {
+    type Op = Op<T, A>;
+    type Validation = DotRange<A>;
+
+    fn validate_op(&self, op: &Self::Op) -> Result<(), Self::Validation> {
+        match op {
+            Op::Add { dot, .. } => self.clock.validate_op(dot),
+            Op::Rm { .. } => Ok(()),
+        }
+    }
+
+    fn apply(&mut self, op: Self::Op) {
+        match op {
+            Op::Add { dot, member } => {
+                if self.clock.get(&dot.actor) >= dot.counter {
+                    // we've already seen this instance
+                    return;
+                }
+                let instance = (dot.actor.clone(), dot.counter);
+                self.clock.apply(dot);
+                if !take_instance(&mut self.deferred, &member, &instance) {
+                    self.entries.entry(member).or_default().insert(instance);
+                }
+            }
+            Op::Rm { dot, member } => {
+                let instance = (dot.actor, dot.counter);
+                if self.clock.get(&instance.0) >= instance.1 {
+                    take_instance(&mut self.entries, &member, &instance);
+                } else {
+                    // we haven't seen the add yet, remove it once it arrives
+                    self.deferred.entry(member).or_default().insert(instance);
+                }
+            }
+        }
+    }
+}
+
+impl<T: Ord + Clone, A: Actor + Debug> CvRDT for ORMultiset<T, A> {
+    type Validation = VacuousValidation;
+
+    fn validate_merge(&self, _other: &Self) -> Result<(), Self::Validation> {
+        Ok(())
+    }
+
+    fn merge(&mut self, mut other: Self) {
+        let mut entries = BTreeMap::new();
+        for (member, ours) in mem::take(&mut self.entries) {
+            let theirs = other.entries.remove(&member).unwrap_or_default();
+            let kept: BTreeSet<_> = ours
+                .iter()
+                .filter(|i| theirs.contains(i) || !seen(&other.clock, i))
+                .cloned()
+                .chain(
+                    theirs
+                        .iter()
+                        .filter(|i| !ours.contains(i) && !seen(&self.clock, i))
+                        .cloned(),
+                )
+                .collect();
+            if !kept.is_empty() {
+                entries.insert(member, kept);
+            }
+        }
+        for (member, theirs) in other.entries {
+            let kept: BTreeSet<_> = theirs
+                .into_iter()
+                .filter(|i| !seen(&self.clock, i))
+                .collect();
+            if !kept.is_empty() {
+                entries.insert(member, kept);
+            }
+        }
+        self.entries = entries;
+        self.clock.merge(other.clock);
+
+        for (member, instances) in other.deferred {
+            self.deferred.entry(member).or_default().extend(instances);
+        }
+        for (member, mut instances) in mem::take(&mut self.deferred) {
+            instances.retain(|i| {
+                if seen(&self.clock, i) {
+                    take_instance(&mut self.entries, &member, i);
+                    false
+                } else {
+                    true
+                }
+            });
+            if !instances.is_empty() {
+                self.deferred.insert(member, instances);
+            }
+        }
+    }
+}
+
+impl<T: Ord, A: Ord> ORMultiset<T, A> {
+    /// Returns a new `ORMultiset` instance.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the number of instances of the member in the multiset.
+    pub fn count(&self, member: &T) -> u64 {
+        self.entries
+            .get(member)
+            .map(|instances| instances.len() as u64)
+            .unwrap_or(0)
+    }
+
+    /// Returns the members of the multiset with their counts.
+    pub fn read(&self) -> BTreeMap<T, u64>
+    where
+        T: Clone,
+    {
+        self.entries
+            .iter()
+            .map(|(member, instances)| (member.clone(), instances.len() as u64))
+            .collect()
+    }
+}
+
+impl<T: Ord, A: Ord + Clone> ORMultiset<T, A> {
+    /// Generate an op adding another instance of the member.
+    pub fn add(&self, member: T, actor: A) -> Op<T, A> {
+        Op::Add {
+            dot: self.clock.inc(actor),
+            member,
+        }
+    }
+
+    /// Generate an op removing one instance of the member, or `None` if the
+    /// multiset holds no instance of it.
+    ///
+    /// The greatest instance is removed, so replicas that observed the same
+    /// instances remove the same one.
+    pub fn remove(&self, member: &T) -> Option<Op<T, A>>
+    where
+        T: Clone,
+    {
+        let (actor, counter) = self.entries.get(member)?.iter().next_back()?;
+        Some(Op::Rm {
+            dot: Dot::new(actor.clone(), *counter),
+            member: member.clone(),
+        })
+    }
+}
+
+fn seen<A: Ord>(clock: &VClock<A>, (actor, counter): &(A, u64)) -> bool {
+    clock.get(actor) >= *counter
+}
+
+/// Remove the instance of the member, returning whether it was present.
+fn take_instance<T: Ord, A: Ord>(
+    instances: &mut BTreeMap<T, BTreeSet<(A, u64)>>,
+    member: &T,
+    instance: &(A, u64),
+) -> bool {
+    let set = match instances.get_mut(member) {
+        Some(set) => set,
+        None => return false,
+    };
+    let removed = set.remove(instance);
+    if set.is_empty() {
+        instances.remove(member);
+    }
+    removed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_add_and_remove_converge() {
+        let mut a = ORMultiset::new();
+        a.apply(a.add(1u8, 'A'));
+        a.apply(a.add(1, 'A'));
+
+        let mut b = a.clone();
+        b.apply(b.remove(&1).unwrap());
+        a.apply(a.add(2, 'A'));
+
+        let mut ab = a.clone();
+        ab.merge(b.clone());
+        b.merge(a);
+
+        assert_eq!(ab, b);
+        assert_eq!(ab.count(&1), 1);
+        assert_eq!(ab.read(), vec![(1, 1), (2, 1)].into_iter().collect());
+    }
+
+    #[test]
+    fn test_remove_before_add_is_deferred() {
+        let mut a = ORMultiset::new();
+        let add = a.add(1u8, 'A');
+        a.apply(add.clone());
+        let rm = a.remove(&1).unwrap();
+
+        let mut b = ORMultiset::new();
+        b.apply(rm);
+        assert_eq!(b.count(&1), 0);
+        b.apply(add);
+        assert_eq!(b.count(&1), 0);
+        assert_eq!(b.read(), BTreeMap::new());
+    }
+
+    #[test]
+    fn test_remove_absent_member() {
+        let set: ORMultiset<u8, char> = ORMultiset::new();
+        assert_eq!(set.remove(&1), None);
+    }
+}
@@ -1,23 +1,186 @@
 /// Observed-Remove Set With Out Tombstones (ORSWOT), ported directly from `riak_dt`.
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::mem;
+use std::sync::mpsc::{self, Receiver, Sender};
 
 use serde::{Deserialize, Serialize};
 
 use crate::ctx::{AddCtx, ReadCtx, RmCtx};
+use crate::gcounter::MergeProgress;
 use crate::quickcheck::{Arbitrary, Gen};
-use crate::{CmRDT, CvRDT, Dot, ResetRemove, VClock};
+use crate::summary::CrdtSummary;
+#[cfg(feature = "json")]
+use crate::vclock::CanonicalPairs;
+#[cfg(feature = "json")]
+use crate::CanonicalBytes;
+use crate::{
+    Actor, CmRDT, Compact, CrdtError, CvRDT, CvRDTIdentity, Dot, DotRange, ResetRemove, VClock,
+};
 
 /// `Orswot` is an add-biased or-set without tombstones ported from
 /// the riak_dt CRDT library.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Orswot<M: Hash + Eq, A: Ord + Hash> {
     pub(crate) clock: VClock<A>,
     pub(crate) entries: HashMap<M, VClock<A>>,
     pub(crate) deferred: HashMap<VClock<A>, HashSet<M>>,
+    #[serde(skip)]
+    pub(crate) capacity: Option<usize>,
+    #[serde(skip)]
+    pub(crate) subscribers: Subscribers<M>,
+    #[serde(skip, default = "RemovalLog::unknown")]
+    pub(crate) removals: RemovalLog<M, A>,
+}
+
+/// The senders registered with `Orswot::subscribe`, with the members
+/// touched since the last events were sent.
+///
+/// Subscriptions are local to a replica, they are neither serialized nor
+/// carried over to clones.
+pub(crate) struct Subscribers<M> {
+    senders: Vec<Sender<SetEvent<M>>>,
+    touched: HashMap<M, bool>,
+}
+
+impl<M> Default for Subscribers<M> {
+    fn default() -> Self {
+        Subscribers {
+            senders: Vec::new(),
+            touched: HashMap::new(),
+        }
+    }
+}
+
+impl<M> Clone for Subscribers<M> {
+    fn clone(&self) -> Self {
+        Default::default()
+    }
+}
+
+impl<M> Debug for Subscribers<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} subscribers", self.senders.len())
+    }
+}
+
+impl<M: Hash + Eq + Clone> Subscribers<M> {
+    /// Record that the member is about to be added or removed, `present`
+    /// being whether it's in the set now. Only the first touch of a member
+    /// since the last `flush` is kept, so only its net change is sent.
+    fn touch(&mut self, member: &M, present: bool) {
+        if !self.senders.is_empty() && !self.touched.contains_key(member) {
+            self.touched.insert(member.clone(), present);
+        }
+    }
+
+    /// Send an event for every touched member whose presence changed,
+    /// removes before adds.
+    fn flush<A: Ord>(&mut self, entries: &HashMap<M, VClock<A>>) {
+        let (mut events, added): (Vec<_>, Vec<_>) = mem::take(&mut self.touched)
+            .into_iter()
+            .filter(|(member, present)| entries.contains_key(member) != *present)
+            .map(|(member, present)| {
+                if present {
+                    SetEvent::Removed(member)
+                } else {
+                    SetEvent::Added(member)
+                }
+            })
+            .partition(|event| matches!(event, SetEvent::Removed(_)));
+        events.extend(added);
+        if events.is_empty() {
+            return;
+        }
+        self.senders.retain(|subscriber| {
+            events
+                .iter()
+                .all(|event| subscriber.send(event.clone()).is_ok())
+        });
+    }
+}
+
+/// The dots each member lost since the set was created, keyed by the
+/// clock of the set when they were lost: the removal context
+/// `Orswot::delta_since` sends to replicas that still hold those dots.
+///
+/// Like subscriptions the log is local to a replica, it isn't serialized.
+/// A deserialized set doesn't know what was removed before it was written,
+/// so its log is unknown.
+#[derive(Debug, Clone)]
+pub(crate) struct RemovalLog<M: Hash + Eq, A: Ord + Hash> {
+    log: Option<HashMap<VClock<A>, Losses<M, A>>>,
+}
+
+/// The dots lost by each member.
+type Losses<M, A> = HashMap<M, VClock<A>>;
+
+impl<M: Hash + Eq, A: Ord + Hash> Default for RemovalLog<M, A> {
+    fn default() -> Self {
+        RemovalLog {
+            log: Some(HashMap::new()),
+        }
+    }
+}
+
+impl<M: Hash + Eq, A: Ord + Hash> RemovalLog<M, A> {
+    /// A log of a set whose earlier removes are not known.
+    fn unknown() -> Self {
+        RemovalLog { log: None }
+    }
+
+    /// Drop the dots lost while the set's clock was below `stable`, no
+    /// replica still holds them.
+    fn prune(&mut self, stable: &VClock<A>) {
+        if let Some(log) = &mut self.log {
+            log.retain(|stamp, _| stamp.partial_cmp(stable) != Some(Ordering::Less));
+        }
+    }
+}
+
+impl<M: Hash + Eq + Clone, A: Ord + Hash + Clone> RemovalLog<M, A> {
+    /// Record that the member lost the dots of `before` that `after`, its
+    /// clock now, no longer holds. `stamp` is the clock of the set.
+    fn record(&mut self, stamp: &VClock<A>, member: &M, before: &VClock<A>, after: &VClock<A>) {
+        let lost = before.clone_without(after);
+        if let (Some(log), false) = (&mut self.log, lost.is_empty()) {
+            log.entry(stamp.clone())
+                .or_default()
+                .entry(member.clone())
+                .or_default()
+                .apply_dots(lost.dots.into_iter().map(|(a, c)| Dot::new(a, c)));
+        }
+    }
+}
+
+/// Returned when an op would grow a bounded `Orswot` past its capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityExceeded {
+    /// The capacity of the set that rejected the op
+    pub capacity: usize,
+}
+
+impl Display for CapacityExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "op would grow set past its capacity of {}",
+            self.capacity
+        )
+    }
+}
+
+impl std::error::Error for CapacityExceeded {}
+
+impl<A> From<CapacityExceeded> for CrdtError<A> {
+    fn from(err: CapacityExceeded) -> Self {
+        CrdtError::CapacityExceeded {
+            capacity: err.capacity,
+        }
+    }
 }
 
 /// Op's define an edit to an Orswot, Op's must be replayed in the exact order
@@ -40,6 +203,120 @@ pub enum Op<M, A: Ord> {
         /// Members to remove
         members: Vec<M>,
     },
+    /// Atomically remove a member and add another in its place
+    ///
+    /// This variant is a breaking change to `Op`: exhaustive matches on it,
+    /// and peers on earlier releases decoding it, need updating.
+    Rename {
+        /// witnessing dot of the add
+        dot: Dot<A>,
+        /// witnessing clock of the remove
+        clock: VClock<A>,
+        /// Member to remove
+        old: M,
+        /// Member to add
+        new: M,
+    },
+}
+
+/// A logical change to the members of an `Orswot`, see `event_of`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SetEvent<M> {
+    /// The member was added
+    Added(M),
+    /// The member was removed
+    Removed(M),
+}
+
+/// Translate an op into the add and remove events it represents, in the
+/// order they take effect, e.g. to feed a change-data-capture pipeline as
+/// ops are applied.
+///
+/// A batch op expands into an event per member and a rename into a remove
+/// followed by an add. The events describe the op, not its effect on any
+/// particular set: removing an absent member still yields `Removed`, so
+/// dedup redelivered ops first, or use `Orswot::merge_observe` to learn
+/// which members actually changed.
+///
+/// ```
+/// use crdts::orswot::{event_of, SetEvent};
+/// use crdts::Orswot;
+///
+/// let set: Orswot<&str, &str> = Orswot::new();
+/// let op = set.add_all(vec!["a", "b"], set.read_ctx().derive_add_ctx("A"));
+/// assert_eq!(event_of(&op), vec![SetEvent::Added("a"), SetEvent::Added("b")]);
+/// ```
+pub fn event_of<M: Clone, A: Ord>(op: &Op<M, A>) -> Vec<SetEvent<M>> {
+    match op {
+        Op::Add { members, .. } => members.iter().cloned().map(SetEvent::Added).collect(),
+        Op::Rm { members, .. } => members.iter().cloned().map(SetEvent::Removed).collect(),
+        Op::Rename { old, new, .. } => {
+            vec![SetEvent::Removed(old.clone()), SetEvent::Added(new.clone())]
+        }
+    }
+}
+
+/// Returns true if interleavings of `ops` converge, a fuzz entry point for
+/// checking the set, e.g. from quickcheck.
+///
+/// The ops are spread over 2 to 10 replicas by the actor of their dot, or
+/// any actor of their clock for removes. Each replica applies its ops in a
+/// random interleaving that keeps the ops of every actor in order, and
+/// every replica count must merge into the same causal state. Removes with
+/// an empty clock are skipped. The interleavings are seeded by the number
+/// of ops, so a failure reproduces.
+#[cfg(feature = "testing")]
+pub fn orswot_converges<M, A>(ops: Vec<Op<M, A>>) -> bool
+where
+    M: Hash + Eq + Clone + Debug,
+    A: Ord + Hash + Clone + Debug,
+{
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::collections::VecDeque;
+
+    let mut rng = StdRng::seed_from_u64(ops.len() as u64);
+    let mut result: Option<Orswot<M, A>> = None;
+    for replicas in 2..=10u64 {
+        // the ops of each replica, queued per actor
+        let mut queues: Vec<BTreeMap<&A, VecDeque<&Op<M, A>>>> =
+            (0..replicas).map(|_| BTreeMap::new()).collect();
+        for op in ops.iter() {
+            let actor = match op {
+                Op::Add { dot, .. } | Op::Rename { dot, .. } => Some(&dot.actor),
+                Op::Rm { clock, .. } => clock.dots.keys().next(),
+            };
+            if let Some(actor) = actor {
+                let index = crate::dedup::digest(actor) % replicas;
+                queues[index as usize]
+                    .entry(actor)
+                    .or_default()
+                    .push_back(op);
+            }
+        }
+
+        let mut merged = Orswot::new();
+        for queue in queues {
+            let mut queue: Vec<VecDeque<&Op<M, A>>> = queue.into_values().collect();
+            let mut witness = Orswot::new();
+            while !queue.is_empty() {
+                let actor = rng.gen_range(0, queue.len());
+                if let Some(op) = queue[actor].pop_front() {
+                    witness.apply(op.clone());
+                }
+                if queue[actor].is_empty() {
+                    queue.swap_remove(actor);
+                }
+            }
+            merged.merge(witness);
+        }
+
+        match &result {
+            Some(prev) if prev != &merged => return false,
+            Some(_) => (),
+            None => result = Some(merged),
+        }
+    }
+    true
 }
 
 impl<M: Hash + Eq, A: Ord + Hash> Default for Orswot<M, A> {
@@ -48,6 +325,116 @@ impl<M: Hash + Eq, A: Ord + Hash> Default for Orswot<M, A> {
             clock: Default::default(),
             entries: Default::default(),
             deferred: Default::default(),
+            capacity: None,
+            subscribers: Default::default(),
+            removals: Default::default(),
+        }
+    }
+}
+
+/// Same as `Orswot::causally_equal`, the capacity is local configuration and is not compared.
+impl<M: Hash + Eq, A: Ord + Hash> PartialEq for Orswot<M, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.causally_equal(other)
+    }
+}
+
+impl<M: Hash + Eq, A: Ord + Hash> Eq for Orswot<M, A> {}
+
+#[cfg(feature = "json")]
+impl<M: Hash + Eq + Serialize, A: Ord + Hash + Serialize> CanonicalBytes for Orswot<M, A> {
+    fn canonical_bytes(&self) -> Vec<u8> {
+        Orswot::canonical_bytes(self)
+    }
+}
+
+impl<M: Hash + Eq, A: Ord + Hash> Orswot<M, A> {
+    /// Returns true if both sets have the same causal state: the same clock,
+    /// the same context for every member and the same deferred removes.
+    ///
+    /// Sets with the same members but a different history are not causally
+    /// equal, compare `read().val` to check only the members.
+    pub fn causally_equal(&self, other: &Self) -> bool {
+        self.clock == other.clock
+            && self.entries == other.entries
+            && self.deferred == other.deferred
+    }
+
+    /// Returns a canonical encoding of the set: the JSON triple of the
+    /// canonical pairs of its clock, its members with their clocks and its
+    /// deferred removes, the members ordered by their JSON encoding. Sets
+    /// that are causally equal have the same canonical bytes, whatever the
+    /// iteration order of their hash maps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a member or actor fails to serialize.
+    #[cfg(feature = "json")]
+    pub fn canonical_bytes(&self) -> Vec<u8>
+    where
+        M: Serialize,
+        A: Serialize,
+    {
+        let encode =
+            |member: &M| serde_json::to_string(member).expect("failed to serialize member");
+        let mut entries: Vec<(String, CanonicalPairs<A>)> = self
+            .entries
+            .iter()
+            .map(|(member, clock)| (encode(member), clock.canonical_pairs()))
+            .collect();
+        entries.sort_unstable();
+        let mut deferred: Vec<(CanonicalPairs<A>, Vec<String>)> = self
+            .deferred
+            .iter()
+            .map(|(clock, members)| {
+                let mut members: Vec<String> = members.iter().map(encode).collect();
+                members.sort_unstable();
+                (clock.canonical_pairs(), members)
+            })
+            .collect();
+        deferred.sort_unstable();
+        serde_json::to_vec(&(self.clock.canonical_pairs(), entries, deferred))
+            .expect("failed to serialize actor")
+    }
+
+    /// Subscribe to the membership changes of this replica: every `apply`,
+    /// merge or `reset_remove` that adds or removes members sends a
+    /// `SetEvent` per changed member to the returned receiver, removes
+    /// before adds. The order of the events of a single call is otherwise
+    /// unspecified. `gc` and `compact` never change the membership, so they
+    /// send nothing.
+    ///
+    /// Subscriptions are local, they aren't serialized and clones of the set
+    /// start without subscribers. Dropping the receiver unsubscribes.
+    ///
+    /// ```
+    /// use crdts::orswot::SetEvent;
+    /// use crdts::{CmRDT, Orswot};
+    ///
+    /// let mut set = Orswot::new();
+    /// let events = set.subscribe();
+    /// set.apply(set.add("a", set.read_ctx().derive_add_ctx("A")));
+    /// assert_eq!(events.try_recv(), Ok(SetEvent::Added("a")));
+    /// ```
+    pub fn subscribe(&mut self) -> Receiver<SetEvent<M>> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.senders.push(sender);
+        receiver
+    }
+
+    /// Returns the number of deferred removes, the removes waiting on adds
+    /// we haven't seen yet. This is the history `gc` reclaims.
+    pub fn deferred_len(&self) -> usize {
+        self.deferred.len()
+    }
+
+    /// Returns the number of members together with the number of actors in
+    /// the set's clock and its causal length.
+    pub fn summary(&self) -> CrdtSummary<usize> {
+        CrdtSummary {
+            value: self.entries.len(),
+            actor_count: self.clock.dots.len(),
+            causal_length: self.clock.causal_length(),
         }
     }
 }
@@ -58,22 +445,28 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone + Debug> CmRDT for Orswot<M, A>
 
     fn validate_op(&self, op: &Self::Op) -> Result<(), Self::Validation> {
         match op {
-            Op::Add { dot, .. } => self.clock.validate_op(dot),
+            Op::Add { dot, .. } | Op::Rename { dot, .. } => self.clock.validate_op(dot),
             Op::Rm { .. } => Ok(()),
         }
     }
 
     fn apply(&mut self, op: Self::Op) {
+        self.apply_op(op);
+        self.subscribers.flush(&self.entries);
+    }
+}
+
+impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone + Debug> Orswot<M, A> {
+    fn apply_op(&mut self, op: Op<M, A>) {
         match op {
             Op::Add { dot, members } => {
-                if self.clock.get(&dot.actor) >= dot.counter {
+                if self.clock.dominates_dot(&dot) {
                     // we've already seen this op
                     return;
                 }
 
                 for member in members {
-                    let member_vclock = self.entries.entry(member).or_default();
-                    member_vclock.apply(dot.clone());
+                    self.entry(member).apply(dot.clone());
                 }
 
                 self.clock.apply(dot);
@@ -82,6 +475,23 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone + Debug> CmRDT for Orswot<M, A>
             Op::Rm { clock, members } => {
                 self.apply_rm(members.into_iter().collect(), clock);
             }
+            Op::Rename {
+                dot,
+                clock,
+                old,
+                new,
+            } => {
+                if self.clock.dominates_dot(&dot) {
+                    // we've already seen this op
+                    return;
+                }
+
+                self.apply_rm(std::iter::once(old).collect(), clock);
+                self.apply_op(Op::Add {
+                    dot,
+                    members: vec![new],
+                });
+            }
         }
     }
 }
@@ -109,6 +519,32 @@ impl<M: Debug, A: Debug> Display for Validation<M, A> {
 
 impl<M: Debug, A: Debug> std::error::Error for Validation<M, A> {}
 
+/// The ways `Orswot::merge_verified` can refuse a merge.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MergeError<M, A> {
+    /// The other set is behind us on an actor it is the authority on, the
+    /// actor's counter went backwards. This happens when two replicas
+    /// were configured with the same actor id.
+    CounterRegression {
+        /// The actor whose counter went backwards
+        actor: A,
+        /// Our counter for this actor
+        ours: u64,
+        /// Their counter for this actor
+        theirs: u64,
+    },
+    /// The merge failed `validate_merge`.
+    Invalid(Validation<M, A>),
+}
+
+impl<M: Debug, A: Debug> Display for MergeError<M, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self, f)
+    }
+}
+
+impl<M: Debug, A: Debug> std::error::Error for MergeError<M, A> {}
+
 impl<M: Hash + Eq + Clone + Debug, A: Ord + Hash + Clone + Debug> CvRDT for Orswot<M, A> {
     type Validation = Validation<M, A>;
 
@@ -116,7 +552,7 @@ impl<M: Hash + Eq + Clone + Debug, A: Ord + Hash + Clone + Debug> CvRDT for Orsw
         for (member, clock) in self.entries.iter() {
             for (other_member, other_clock) in other.entries.iter() {
                 for Dot { actor, counter } in clock.iter() {
-                    if other_member != member && other_clock.get(&actor) == counter {
+                    if other_member != member && other_clock.get(actor) == counter {
                         return Err(Validation::DoubleSpentDot {
                             dot: Dot::new(actor.clone(), counter),
                             our_member: member.clone(),
@@ -132,6 +568,9 @@ impl<M: Hash + Eq + Clone + Debug, A: Ord + Hash + Clone + Debug> CvRDT for Orsw
 
     /// Merge combines another `Orswot` with this one.
     fn merge(&mut self, other: Self) {
+        let subscribers = &mut self.subscribers;
+        let removals = &mut self.removals;
+        let stamp = &self.clock;
         self.entries = mem::take(&mut self.entries)
             .into_iter()
             .filter_map(|(entry, mut clock)| {
@@ -141,13 +580,17 @@ impl<M: Hash + Eq + Clone + Debug, A: Ord + Hash + Clone + Debug> CvRDT for Orsw
                     //  2. hasn't seen it
                     if other.clock >= clock {
                         // other has seen this entry and dropped it
+                        subscribers.touch(&entry, true);
+                        removals.record(stamp, &entry, &clock, &VClock::new());
                         None
                     } else {
                         // the other map has not seen this version of this
                         // entry, so add it. But first, we have to remove any
                         // information that may have been known at some point
                         // by the other map about this key and was removed.
+                        let before = clock.clone();
                         clock.reset_remove(&other.clock);
+                        removals.record(stamp, &entry, &before, &clock);
                         Some((entry, clock))
                     }
                 } else {
@@ -161,11 +604,14 @@ impl<M: Hash + Eq + Clone + Debug, A: Ord + Hash + Clone + Debug> CvRDT for Orsw
                 // SUBTLE: this entry is present in both orswots, BUT that doesn't mean we
                 // shouldn't drop it!
                 // Perfectly possible that an item in both sets should be dropped
-                let mut common = VClock::intersection(&clock, &our_clock);
+                let mut common = VClock::intersection(&clock, our_clock);
                 common.merge(clock.clone_without(&self.clock));
                 common.merge(our_clock.clone_without(&other.clock));
+                self.removals
+                    .record(&self.clock, &entry, our_clock, &common);
                 if common.is_empty() {
                     // both maps had seen each others entry and removed them
+                    self.subscribers.touch(&entry, true);
                     self.entries.remove(&entry).unwrap();
                 } else {
                     // we should not drop, as there is information still tracked in
@@ -183,6 +629,7 @@ impl<M: Hash + Eq + Clone + Debug, A: Ord + Hash + Clone + Debug> CvRDT for Orsw
                     // but first, we have to remove the information on this entry
                     // that we have seen and deleted
                     clock.reset_remove(&self.clock);
+                    self.subscribers.touch(&entry, false);
                     self.entries.insert(entry, clock);
                 }
             }
@@ -196,18 +643,228 @@ impl<M: Hash + Eq + Clone + Debug, A: Ord + Hash + Clone + Debug> CvRDT for Orsw
         self.clock.merge(other.clock);
 
         self.apply_deferred();
+        self.subscribers.flush(&self.entries);
+    }
+}
+
+impl<M: Hash + Eq + Clone + Debug, A: Ord + Hash + Clone + Debug> CvRDTIdentity for Orswot<M, A> {
+    /// The empty set with an empty clock.
+    fn identity() -> Self {
+        Default::default()
+    }
+}
+
+impl<M: Hash + Eq + Clone + Debug, A: Ord + Hash + Clone + Debug> Orswot<M, A> {
+    /// Merge another `Orswot` into this one, ignoring any of `other`'s adds
+    /// whose clock is dominated by `barrier`.
+    ///
+    /// `barrier` must be a causally stable lower bound: every replica must have
+    /// seen (and possibly garbage collected through `reset_remove`) all history
+    /// below it. A stale snapshot still carrying adds from that history would
+    /// otherwise resurrect members that were removed long ago.
+    ///
+    /// Entries we are still tracking ourselves are merged as usual.
+    pub fn merge_after(&mut self, other: &Self, barrier: &VClock<A>) {
+        let mut other = other.clone();
+        other.entries = mem::take(&mut other.entries)
+            .into_iter()
+            .filter(|(member, clock)| {
+                let below_barrier = clock <= barrier;
+                self.entries.contains_key(member) || !below_barrier
+            })
+            .collect();
+        self.merge(other);
+    }
+
+    /// Merge another `Orswot` into this one after checking the actors'
+    /// provenance, leaving self untouched if a check fails.
+    ///
+    /// `actor_check` selects the actors `other` is authoritative on, e.g.
+    /// the actor of the replica `other` came from. Those actors only ever
+    /// move forward on their own replica, so if `other` is behind us on any
+    /// of them the actor id must have been reused by another replica.
+    pub fn merge_verified(
+        &mut self,
+        other: &Self,
+        actor_check: impl Fn(&A) -> bool,
+    ) -> Result<(), MergeError<M, A>> {
+        for Dot { actor, counter } in self.clock.iter() {
+            let theirs = other.clock.get(actor);
+            if theirs < counter && actor_check(actor) {
+                return Err(MergeError::CounterRegression {
+                    actor: actor.clone(),
+                    ours: counter,
+                    theirs,
+                });
+            }
+        }
+        self.validate_merge(other).map_err(MergeError::Invalid)?;
+        self.merge(other.clone());
+        Ok(())
+    }
+
+    /// Merge another `Orswot` into this one, returning the members that
+    /// became present and the members that became absent due to the merge.
+    ///
+    /// The order of the members in either list is unspecified.
+    pub fn merge_observe(&mut self, other: &Self) -> (Vec<M>, Vec<M>) {
+        let before: HashSet<M> = self.entries.keys().cloned().collect();
+        self.merge(other.clone());
+
+        let appeared = self
+            .entries
+            .keys()
+            .filter(|member| !before.contains(member))
+            .cloned()
+            .collect();
+        let disappeared = before
+            .into_iter()
+            .filter(|member| !self.entries.contains_key(member))
+            .collect();
+        (appeared, disappeared)
+    }
+
+    /// Merge another `Orswot` into this one, reclaiming the deferred remove
+    /// history below `stable` as the removes are merged, see `gc`. Returns
+    /// the number of dots and deferred removes reclaimed.
+    ///
+    /// Use this to merge a peer with heavy add/remove churn, whose pending
+    /// removes would otherwise pile up in our deferred history: each of its
+    /// removes is collapsed before it is deferred, so our deferred history
+    /// never holds more than the collapsed removes.
+    pub fn merge_limit(&mut self, mut other: Self, stable: &VClock<A>) -> usize {
+        let removes: Vec<_> = mem::take(&mut self.deferred)
+            .into_iter()
+            .chain(mem::take(&mut other.deferred))
+            .collect();
+        self.merge(other);
+
+        let mut floor = self.clock.clone();
+        floor.glb(stable);
+        let mut reclaimed = 0;
+        for (clock, members) in removes {
+            self.reset_members(&members, &clock);
+            if matches!(
+                clock.partial_cmp(&self.clock),
+                None | Some(Ordering::Greater)
+            ) {
+                reclaimed += self.defer_above(members, clock, &floor);
+            }
+        }
+        self.subscribers.flush(&self.entries);
+        reclaimed
+    }
+
+    /// Merge the dots of at most `max_entries` actors of `other` into this
+    /// set, see `GCounter::merge_budgeted`.
+    ///
+    /// An actor's dots merge independently of the other actors' dots, so the
+    /// set is valid between calls, holding the members as merged so far.
+    /// Only actors whose dots still change this set count against the
+    /// budget, so calling this repeatedly with the same set resumes where the
+    /// last call stopped. The deferred removes of `other` are applied by the
+    /// call that merges its last actor, after a call returns
+    /// `MergeProgress::Complete`, self equals a full merge.
+    ///
+    /// Each call still scans both sets to find the actors left to merge, the
+    /// budget bounds the actors merged, not the reads.
+    pub fn merge_budgeted(&mut self, other: &Self, max_entries: usize) -> MergeProgress {
+        let mut pending: BTreeSet<&A> = other
+            .clock
+            .iter()
+            .filter(|dot| dot.counter > self.clock.get(dot.actor))
+            .map(|dot| dot.actor)
+            .collect();
+        for (member, clock) in self.entries.iter() {
+            let theirs = other.entries.get(member);
+            pending.extend(
+                clock
+                    .iter()
+                    .filter(|dot| other.removed(theirs, dot))
+                    .map(|dot| dot.actor),
+            );
+        }
+        let progress = if pending.len() > max_entries {
+            MergeProgress::Pending
+        } else {
+            MergeProgress::Complete
+        };
+        let merging: Vec<A> = pending.into_iter().take(max_entries).cloned().collect();
+
+        let subscribers = &mut self.subscribers;
+        let removals = &mut self.removals;
+        let stamp = &self.clock;
+        self.entries.retain(|member, clock| {
+            let theirs = other.entries.get(member);
+            let before = clock.clone();
+            for actor in merging.iter() {
+                let dot = Dot::new(actor, clock.get(actor));
+                if dot.counter > 0 && other.removed(theirs, &dot) {
+                    clock.dots.remove(actor);
+                }
+            }
+            removals.record(stamp, member, &before, clock);
+            if clock.is_empty() {
+                subscribers.touch(member, true);
+            }
+            !clock.is_empty()
+        });
+        for (member, clock) in other.entries.iter() {
+            for actor in merging.iter() {
+                let counter = clock.get(actor);
+                if counter > self.clock.get(actor) {
+                    let dot = Dot::new(actor.clone(), counter);
+                    self.entry(member.clone()).apply(dot);
+                }
+            }
+        }
+        for actor in merging {
+            let counter = other.clock.get(&actor);
+            self.clock.apply(Dot::new(actor, counter));
+        }
+
+        if progress == MergeProgress::Complete {
+            for (rm_clock, members) in other.deferred.iter() {
+                self.apply_rm(members.clone(), rm_clock.clone());
+            }
+        }
+        self.apply_deferred();
+        self.subscribers.flush(&self.entries);
+        progress
+    }
+
+    /// Returns true if this set has seen the dot and removed it: our clock
+    /// covers it, but `ours`, our clock of the member it witnesses, doesn't
+    /// hold it.
+    fn removed(&self, ours: Option<&VClock<A>>, dot: &Dot<&A>) -> bool {
+        dot.counter <= self.clock.get(dot.actor)
+            && ours.map(|clock| clock.get(dot.actor)) != Some(dot.counter)
+    }
+}
+
+impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Compact<A> for Orswot<M, A> {
+    /// Drops the history below `stable` from the deferred removes, see `gc`.
+    ///
+    /// The `Orswot` keeps no tombstones, deferred removes and the local log
+    /// of removes read by `delta_since` are the only history it holds.
+    fn compact(&mut self, stable: &VClock<A>) {
+        self.gc(stable);
     }
 }
 
 impl<M: Hash + Clone + Eq, A: Ord + Hash> ResetRemove<A> for Orswot<M, A> {
     fn reset_remove(&mut self, clock: &VClock<A>) {
-        self.clock.reset_remove(&clock);
+        self.clock.reset_remove(clock);
+        // our clock went back, the log's clocks no longer order our history
+        self.removals = RemovalLog::unknown();
 
+        let subscribers = &mut self.subscribers;
         self.entries = mem::take(&mut self.entries)
             .into_iter()
             .filter_map(|(val, mut val_clock)| {
-                val_clock.reset_remove(&clock);
+                val_clock.reset_remove(clock);
                 if val_clock.is_empty() {
+                    subscribers.touch(&val, true);
                     None
                 } else {
                     Some((val, val_clock))
@@ -218,7 +875,7 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash> ResetRemove<A> for Orswot<M, A> {
         self.deferred = mem::take(&mut self.deferred)
             .into_iter()
             .filter_map(|(mut vclock, deferred)| {
-                vclock.reset_remove(&clock);
+                vclock.reset_remove(clock);
                 if vclock.is_empty() {
                     None
                 } else {
@@ -226,6 +883,7 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash> ResetRemove<A> for Orswot<M, A> {
                 }
             })
             .collect();
+        self.subscribers.flush(&self.entries);
     }
 }
 
@@ -235,6 +893,94 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Orswot<M, A> {
         Default::default()
     }
 
+    /// Validate the op before applying it, ops from untrusted sources should
+    /// go through here rather than `apply`.
+    ///
+    /// Besides `validate_op`, the op is checked against the capacity of a
+    /// bounded set, see `Orswot::with_capacity`.
+    pub fn apply_checked(&mut self, op: Op<M, A>) -> Result<(), CrdtError<A>>
+    where
+        A: Debug,
+    {
+        self.validate_op(&op)?;
+        self.check_capacity(&op)?;
+        self.apply(op);
+        Ok(())
+    }
+
+    /// Check the invariants of a set, e.g. one deserialized from an untrusted
+    /// source: the context of every member must be covered by the set's clock.
+    pub fn validate_state(&self) -> Result<(), CrdtError<A>> {
+        let member_clocks = self.entries.values();
+        for dot in member_clocks.flat_map(|clock| clock.iter()) {
+            let seen = self.clock.get(dot.actor);
+            if seen == 0 {
+                return Err(CrdtError::UnknownActor {
+                    actor: dot.actor.clone(),
+                });
+            } else if dot.counter > seen {
+                return Err(CrdtError::DotGap(DotRange {
+                    actor: dot.actor.clone(),
+                    counter_range: seen + 1..dot.counter.saturating_add(1),
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a new `Orswot` that refuses to hold more than `capacity` members.
+    ///
+    /// An add op that would grow the set past `capacity` distinct present
+    /// members is rejected by `apply_checked`, or by `check_capacity` ahead
+    /// of applying it. Removes, and re-adds of present members, are never
+    /// rejected.
+    ///
+    /// `apply` itself always applies the op. Skipping an add there would
+    /// still move our clock past its dot once the actor's next op arrives,
+    /// and merging us would then remove the member from every peer.
+    ///
+    /// The capacity is a local safety valve against a buggy or malicious
+    /// peer, not a replicated invariant. It isn't serialized and isn't
+    /// enforced on `merge`, and since a rejected dot never enters our clock,
+    /// later ops from that actor will fail `validate_op`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Orswot {
+            capacity: Some(capacity),
+            ..Default::default()
+        }
+    }
+
+    /// Check that applying the op would not grow the set past its capacity.
+    pub fn check_capacity(&self, op: &Op<M, A>) -> Result<(), CapacityExceeded> {
+        match op {
+            Op::Add { members, .. } => self.new_members_fit(members),
+            Op::Rm { .. } => Ok(()),
+            Op::Rename {
+                clock, old, new, ..
+            } => match self.entries.get(old) {
+                // the remove frees the place of `old`, at most `new` takes it
+                Some(old_clock) if old_clock <= clock => Ok(()),
+                _ => self.new_members_fit(std::slice::from_ref(new)),
+            },
+        }
+    }
+
+    fn new_members_fit(&self, members: &[M]) -> Result<(), CapacityExceeded> {
+        let capacity = match self.capacity {
+            Some(capacity) => capacity,
+            None => return Ok(()),
+        };
+        let new_members: HashSet<&M> = members
+            .iter()
+            .filter(|m| !self.entries.contains_key(m))
+            .collect();
+        if self.entries.len() + new_members.len() > capacity {
+            Err(CapacityExceeded { capacity })
+        } else {
+            Ok(())
+        }
+    }
+
     /// Return a snapshot of the ORSWOT clock
     pub fn clock(&self) -> VClock<A> {
         self.clock.clone()
@@ -256,6 +1002,26 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Orswot<M, A> {
         }
     }
 
+    /// Add a single element under an externally supplied context, e.g. when
+    /// importing members from another system.
+    ///
+    /// An add op carries a single dot, so this returns one add per actor in
+    /// `ctx`. Applying all of them leaves the member with exactly `ctx` as
+    /// its context, provided the set hasn't already seen any of its dots.
+    ///
+    /// The context must be causally valid: its dots must be fresh, never
+    /// used for another member, or merges with replicas holding that member
+    /// will misbehave. Dots skipping ahead of the set's clock are accepted
+    /// by `apply` but flagged by `validate_op`.
+    pub fn add_with_ctx(&self, member: M, ctx: VClock<A>) -> Vec<Op<M, A>> {
+        ctx.into_iter()
+            .map(|dot| Op::Add {
+                dot,
+                members: vec![member.clone()],
+            })
+            .collect()
+    }
+
     /// Remove a member with a witnessing ctx.
     pub fn rm(&self, member: M, ctx: RmCtx<A>) -> Op<M, A> {
         Op::Rm {
@@ -272,16 +1038,116 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Orswot<M, A> {
         }
     }
 
-    /// Remove members using a witnessing clock.
-    fn apply_rm(&mut self, members: HashSet<M>, clock: VClock<A>) {
-        for member in members.iter() {
-            if let Some(member_clock) = self.entries.get_mut(&member) {
-                member_clock.reset_remove(&clock);
-                if member_clock.is_empty() {
-                    self.entries.remove(&member);
-                }
+    /// Remove every member currently in the set.
+    ///
+    /// The remove is witnessed by the current clock, so members added
+    /// concurrently on other replicas survive the clear (adds win).
+    ///
+    /// Unlike `add` and `rename` this takes no actor: a remove spends no
+    /// dot, its witnessing clock is all the context it carries, the same as
+    /// `rm_all` with the read context of every present member.
+    pub fn clear(&self) -> Op<M, A> {
+        let read_ctx = self.read();
+        Op::Rm {
+            clock: read_ctx.rm_clock,
+            members: read_ctx.val.into_iter().collect(),
+        }
+    }
+
+    /// Generate an op adding the member, or `None` if it is already present,
+    /// saving a dot and an op on the wire for idempotent adds.
+    ///
+    /// `None` only reflects what this replica has seen, a concurrent remove
+    /// elsewhere still wins over the earlier add as usual.
+    pub fn add_if_absent(&self, member: M, actor: A) -> Option<Op<M, A>> {
+        if self.entries.contains_key(&member) {
+            return None;
+        }
+        Some(Op::Add {
+            dot: self.clock.inc(actor),
+            members: vec![member],
+        })
+    }
+
+    /// Rename a member: remove `old` and add `new` in a single op, so that
+    /// no replica observes the set holding neither or both of them.
+    ///
+    /// The remove only affects what this replica has seen of `old`; like a
+    /// plain remove, concurrent adds of `old` survive it. Concurrent renames
+    /// of the same member each add their `new` member, every replica
+    /// converges on holding all of them.
+    ///
+    /// ```
+    /// use crdts::{CmRDT, Orswot};
+    ///
+    /// let mut set = Orswot::new();
+    /// set.apply(set.add("colour", set.read_ctx().derive_add_ctx("A")));
+    ///
+    /// set.apply(set.rename(&"colour", "color", "A"));
+    /// assert!(!set.contains(&"colour").val);
+    /// assert!(set.contains(&"color").val);
+    /// ```
+    pub fn rename(&self, old: &M, new: M, actor: A) -> Op<M, A> {
+        Op::Rename {
+            dot: self.clock.inc(actor),
+            clock: self.clock.clone(),
+            old: old.clone(),
+            new,
+        }
+    }
+
+    /// Reclaim the history held by deferred removes below `stable`, a clock
+    /// every replica is known to have seen. Returns the number of dots and
+    /// deferred removes reclaimed.
+    ///
+    /// Within the removes waiting on unseen adds, every actor whose dots
+    /// we've already seen and `stable` covers is dropped from the remove's
+    /// clock: those dots were removed when the remove arrived and nobody
+    /// can add them again. Removes left waiting on the same clock
+    /// collapse into one. Membership is unchanged, now and once the awaited
+    /// adds arrive, as long as `stable` is a true lower bound.
+    ///
+    /// The removes `delta_since` carries that were made before our clock
+    /// passed `stable` are dropped too, no replica can still need them.
+    pub fn gc(&mut self, stable: &VClock<A>) -> usize {
+        let mut floor = self.clock.clone();
+        floor.glb(stable);
+        self.removals.prune(stable);
+
+        mem::take(&mut self.deferred)
+            .into_iter()
+            .map(|(clock, members)| self.defer_above(members, clock, &floor))
+            .sum()
+    }
+
+    /// Defer a remove waiting on unseen adds, dropping the actors of its
+    /// clock that `floor` covers, see `gc`. Returns the number of dots and
+    /// deferred removes reclaimed.
+    fn defer_above(
+        &mut self,
+        members: HashSet<M>,
+        mut clock: VClock<A>,
+        floor: &VClock<A>,
+    ) -> usize {
+        let dots = clock.dots.len();
+        clock.reset_remove(floor);
+        let mut reclaimed = dots - clock.dots.len();
+
+        match self.deferred.get_mut(&clock) {
+            Some(existing) => {
+                existing.extend(members);
+                reclaimed += 1;
+            }
+            None => {
+                self.deferred.insert(clock, members);
             }
         }
+        reclaimed
+    }
+
+    /// Remove members using a witnessing clock.
+    fn apply_rm(&mut self, members: HashSet<M>, clock: VClock<A>) {
+        self.reset_members(&members, &clock);
 
         match clock.partial_cmp(&self.clock) {
             None | Some(Ordering::Greater) => {
@@ -295,9 +1161,36 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Orswot<M, A> {
         }
     }
 
+    /// The clock of the member, inserting an empty clock if it's absent.
+    fn entry(&mut self, member: M) -> &mut VClock<A> {
+        match self.entries.entry(member) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                self.subscribers.touch(entry.key(), false);
+                entry.insert(VClock::new())
+            }
+        }
+    }
+
+    /// Drop the dots of these members that the clock has seen.
+    fn reset_members(&mut self, members: &HashSet<M>, clock: &VClock<A>) {
+        for member in members.iter() {
+            if let Some(member_clock) = self.entries.get_mut(member) {
+                let before = member_clock.clone();
+                member_clock.reset_remove(clock);
+                self.removals
+                    .record(&self.clock, member, &before, member_clock);
+                if member_clock.is_empty() {
+                    self.subscribers.touch(member, true);
+                    self.entries.remove(member);
+                }
+            }
+        }
+    }
+
     /// Check if the set contains a member
     pub fn contains(&self, member: &M) -> ReadCtx<bool, A> {
-        let member_clock_opt = self.entries.get(&member);
+        let member_clock_opt = self.entries.get(member);
         let exists = member_clock_opt.is_some();
         ReadCtx {
             add_clock: self.clock.clone(),
@@ -306,6 +1199,106 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Orswot<M, A> {
         }
     }
 
+    /// Check if the set contains every one of the given members.
+    /// Returns `true` for no members.
+    ///
+    /// The remove context covers the present members among them.
+    pub fn contains_all(&self, members: &[M]) -> ReadCtx<bool, A> {
+        let (present, rm_clock) = self.present(members);
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock,
+            val: present == members.len(),
+        }
+    }
+
+    /// Check if the set contains at least one of the given members.
+    /// Returns `false` for no members.
+    ///
+    /// The remove context covers the present members among them.
+    pub fn contains_any(&self, members: &[M]) -> ReadCtx<bool, A> {
+        let (present, rm_clock) = self.present(members);
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock,
+            val: present > 0,
+        }
+    }
+
+    /// The number of the given members present, with their merged clocks.
+    fn present(&self, members: &[M]) -> (usize, VClock<A>) {
+        let mut clock = VClock::new();
+        let mut present = 0;
+        for member_clock in members.iter().filter_map(|m| self.entries.get(m)) {
+            clock.apply_dots(
+                member_clock
+                    .iter()
+                    .map(|dot| Dot::new(dot.actor.clone(), dot.counter)),
+            );
+            present += 1;
+        }
+        (present, clock)
+    }
+
+    /// Returns the members where self holds something `other` lacks, and
+    /// the members where `other` holds something self lacks.
+    ///
+    /// A member differs if it's present on only one side, or if it's present
+    /// on both but one side's add context has dots the other hasn't got.
+    /// Dots below `stable`, a clock every replica is known to have seen, are
+    /// ignored when comparing contexts, so a peer that compacted that history
+    /// away doesn't show up as divergent. A member present on only one side
+    /// is always reported, whatever its context.
+    ///
+    /// A member may be in both lists if both sides added it concurrently.
+    /// The order of the members in either list is unspecified.
+    pub fn diff_stable(&self, other: &Self, stable: &VClock<A>) -> (Vec<M>, Vec<M>) {
+        let unstable = |clock: &VClock<A>| {
+            let mut clock = clock.clone();
+            clock.reset_remove(stable);
+            clock
+        };
+        let ahead = |ours: &VClock<A>, theirs: &VClock<A>| {
+            ours.iter().any(|dot| dot.counter > theirs.get(dot.actor))
+        };
+
+        let mut ours_only = Vec::new();
+        let mut theirs_only = Vec::new();
+        for (member, clock) in self.entries.iter() {
+            match other.entries.get(member) {
+                None => ours_only.push(member.clone()),
+                Some(other_clock) => {
+                    let (ours, theirs) = (unstable(clock), unstable(other_clock));
+                    if ahead(&ours, &theirs) {
+                        ours_only.push(member.clone());
+                    }
+                    if ahead(&theirs, &ours) {
+                        theirs_only.push(member.clone());
+                    }
+                }
+            }
+        }
+        theirs_only.extend(
+            other
+                .entries
+                .keys()
+                .filter(|member| !self.entries.contains_key(member))
+                .cloned(),
+        );
+        (ours_only, theirs_only)
+    }
+
+    /// Check if the set contains a member whose add-context is exactly `expected`.
+    ///
+    /// Use this to guard a remove against clobbering an add that happened
+    /// after `expected` was read, the member's context will have advanced.
+    pub fn contains_at(&self, member: &M, expected: &VClock<A>) -> bool {
+        self.entries
+            .get(member)
+            .map(|clock| clock == expected)
+            .unwrap_or(false)
+    }
+
     /// Gets an iterator over the entries of the `Map`.
     ///
     /// # Examples
@@ -340,6 +1333,19 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Orswot<M, A> {
         })
     }
 
+    /// Gets a lazy iterator over the current members, in no particular order.
+    ///
+    /// Unlike `iter` and `read` this doesn't clone any members or a clock
+    /// per member, use it to stream the members of large sets. The context
+    /// is the set's, as for `read`.
+    pub fn members(&self) -> ReadCtx<impl Iterator<Item = &M>, A> {
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: self.clock.clone(),
+            val: self.entries.keys(),
+        }
+    }
+
     /// Retrieve the current members.
     pub fn read(&self) -> ReadCtx<HashSet<M>, A> {
         ReadCtx {
@@ -349,6 +1355,23 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Orswot<M, A> {
         }
     }
 
+    /// Returns a frozen view of the current members, sorted.
+    ///
+    /// The view is cloned out of the set, ops applied to the set afterwards
+    /// don't affect it. The context is the set's, as for `read`.
+    pub fn snapshot(&self) -> ReadCtx<OrswotView<M>, A>
+    where
+        M: Ord,
+    {
+        let mut members: Vec<M> = self.entries.keys().cloned().collect();
+        members.sort();
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: self.clock.clone(),
+            val: OrswotView { members },
+        }
+    }
+
     /// Retrieve the current read context
     pub fn read_ctx(&self) -> ReadCtx<(), A> {
         ReadCtx {
@@ -358,6 +1381,143 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Orswot<M, A> {
         }
     }
 
+    /// Returns the part of this set a replica whose state this set has
+    /// been in, at `clock`, needs to catch up: merging the delta into that
+    /// replica has the same effect as merging the full set.
+    ///
+    /// The delta holds the clock of every actor that moved past `clock`, the
+    /// members with a dot of those actors and the removes since `clock` as
+    /// deferred removes, along with our own deferred removes. Removes don't
+    /// move the clock, so this set keeps a local log of the dots its
+    /// members lost, `gc` trims it. A set that was deserialized or
+    /// `reset_remove`d doesn't know its earlier removes, its delta is the
+    /// full set.
+    ///
+    /// ```
+    /// use crdts::{CmRDT, CvRDT, Orswot};
+    ///
+    /// let mut a = Orswot::new();
+    /// a.apply(a.add(1, a.read_ctx().derive_add_ctx("A")));
+    /// a.apply(a.add(2, a.read_ctx().derive_add_ctx("B")));
+    /// let mut b = a.clone();
+    ///
+    /// a.apply(a.rm(1, a.contains(&1).derive_rm_ctx()));
+    /// a.apply(a.add(3, a.read_ctx().derive_add_ctx("C")));
+    ///
+    /// let delta = a.delta_since(&b.clock());
+    /// assert!(!delta.contains(&2).val);
+    /// b.merge(delta);
+    /// assert_eq!(b, a);
+    /// ```
+    pub fn delta_since(&self, clock: &VClock<A>) -> Self {
+        let log = match &self.removals.log {
+            Some(log) => log,
+            None => {
+                return Orswot {
+                    clock: self.clock.clone(),
+                    entries: self.entries.clone(),
+                    deferred: self.deferred.clone(),
+                    ..Default::default()
+                }
+            }
+        };
+
+        let moved = self.clock.clone_without(clock);
+        let entries = self
+            .entries
+            .iter()
+            .filter(|(_, member_clock)| member_clock.iter().any(|dot| moved.get(dot.actor) > 0))
+            .map(|(member, member_clock)| (member.clone(), member_clock.clone()))
+            .collect();
+
+        let mut deferred = self.deferred.clone();
+        let since = |stamp: &VClock<A>| stamp.partial_cmp(clock) != Some(Ordering::Less);
+        for (_, lost) in log.iter().filter(|(stamp, _)| since(stamp)) {
+            for (member, lost_clock) in lost {
+                deferred
+                    .entry(lost_clock.clone())
+                    .or_default()
+                    .insert(member.clone());
+            }
+        }
+
+        Orswot {
+            clock: moved,
+            entries,
+            deferred,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a projection of this set with `f` applied to every member,
+    /// keeping the causal context of each member so the projection can be
+    /// merged with projections of other replicas.
+    ///
+    /// `f` must be injective for the projections to converge. If two members
+    /// map to the same value their contexts are merged, which can resurrect
+    /// the value a remove was meant to clear.
+    pub fn map_members<U: Hash + Eq + Clone, F: Fn(&M) -> U>(&self, f: F) -> Orswot<U, A> {
+        let mut entries: HashMap<U, VClock<A>> = HashMap::new();
+        for (member, clock) in self.entries.iter() {
+            entries.entry(f(member)).or_default().apply_dots(
+                clock
+                    .iter()
+                    .map(|dot| Dot::new(dot.actor.clone(), dot.counter)),
+            );
+        }
+
+        let deferred = self
+            .deferred
+            .iter()
+            .map(|(clock, members)| (clock.clone(), members.iter().map(&f).collect()))
+            .collect();
+
+        Orswot {
+            clock: self.clock.clone(),
+            entries,
+            deferred,
+            capacity: self.capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Rewrite every actor in this set's clocks through `f`, e.g. when
+    /// migrating the set to a cluster with a different actor id scheme.
+    ///
+    /// `f` must be injective, and every replica must be remapped with the
+    /// same `f`, for the remapped sets to converge. If two actors map to the
+    /// same actor their counters are merged by taking the max, losing their
+    /// causal history.
+    pub fn remap_actors<B: Actor>(self, f: impl Fn(&A) -> B) -> Orswot<M, B> {
+        let remap = |clock: VClock<A>| VClock {
+            dots: clock.dots.into_iter().fold(
+                BTreeMap::new(),
+                |mut dots: BTreeMap<B, u64>, (actor, counter)| {
+                    let remapped = dots.entry(f(&actor)).or_default();
+                    *remapped = (*remapped).max(counter);
+                    dots
+                },
+            ),
+        };
+
+        let mut deferred: HashMap<VClock<B>, HashSet<M>> = HashMap::new();
+        for (clock, members) in self.deferred {
+            deferred.entry(remap(clock)).or_default().extend(members);
+        }
+
+        Orswot {
+            clock: remap(self.clock),
+            entries: self
+                .entries
+                .into_iter()
+                .map(|(member, clock)| (member, remap(clock)))
+                .collect(),
+            deferred,
+            capacity: self.capacity,
+            ..Default::default()
+        }
+    }
+
     fn apply_deferred(&mut self) {
         let deferred = mem::take(&mut self.deferred);
         for (clock, entries) in deferred.into_iter() {
@@ -366,6 +1526,74 @@ impl<M: Hash + Clone + Eq, A: Ord + Hash + Clone> Orswot<M, A> {
     }
 }
 
+impl<K: Ord + Hash + Clone, V: Ord + Hash + Clone, A: Ord + Hash> Orswot<(K, V), A> {
+    /// Collect a set of key-value pairs into a map.
+    ///
+    /// When several present pairs share a key, the pair whose add context
+    /// has the greater `causal_length` wins, ties broken by the greater
+    /// value, so all replicas pick the same pair. A member's add context only
+    /// holds the dots that added it, so this is not last-writer-wins; remove
+    /// the old pair when overwriting a key rather than relying on this rule.
+    ///
+    /// ```
+    /// use crdts::{CmRDT, Orswot};
+    ///
+    /// let mut set = Orswot::new();
+    /// set.apply(set.add(("name", "alice"), set.read_ctx().derive_add_ctx("A")));
+    /// set.apply(set.add(("name", "bob"), set.read_ctx().derive_add_ctx("A")));
+    ///
+    /// assert_eq!(set.read_map().val.get("name"), Some(&"bob"));
+    /// ```
+    pub fn read_map(&self) -> ReadCtx<BTreeMap<K, V>, A>
+    where
+        A: Clone,
+    {
+        let mut map: BTreeMap<K, (u64, V)> = BTreeMap::new();
+        for ((key, val), clock) in self.entries.iter() {
+            let rank = (clock.causal_length(), val.clone());
+            match map.get(key) {
+                Some(winner) if *winner >= rank => (),
+                _ => {
+                    map.insert(key.clone(), rank);
+                }
+            }
+        }
+        ReadCtx {
+            add_clock: self.clock.clone(),
+            rm_clock: self.clock.clone(),
+            val: map.into_iter().map(|(key, (_, val))| (key, val)).collect(),
+        }
+    }
+}
+
+/// A frozen, read-only view of the members of an `Orswot`, see `Orswot::snapshot`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OrswotView<M> {
+    members: Vec<M>,
+}
+
+impl<M: Ord> OrswotView<M> {
+    /// Check if the view contains a member.
+    pub fn contains(&self, member: &M) -> bool {
+        self.members.binary_search(member).is_ok()
+    }
+
+    /// Gets an iterator over the members of the view in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = &M> {
+        self.members.iter()
+    }
+
+    /// The number of members in the view.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns `true` if the view has no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
 impl<A: Ord + Hash + Arbitrary + Debug, M: Hash + Eq + Arbitrary> Arbitrary for Op<M, A> {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         let dot = Dot::arbitrary(g);
@@ -377,9 +1605,15 @@ impl<A: Ord + Hash + Arbitrary + Debug, M: Hash + Eq + Arbitrary> Arbitrary for
         }
         let members: Vec<_> = members_set.into_iter().collect();
 
-        match u8::arbitrary(g) % 2 {
+        match u8::arbitrary(g) % 3 {
             0 => Op::Add { members, dot },
             1 => Op::Rm { members, clock },
+            2 => Op::Rename {
+                dot,
+                clock,
+                old: M::arbitrary(g),
+                new: M::arbitrary(g),
+            },
             _ => panic!("tried to generate invalid op"),
         }
     }
@@ -423,6 +1657,21 @@ impl<A: Ord + Hash + Arbitrary + Debug, M: Hash + Eq + Arbitrary> Arbitrary for
                     })
                 });
             }
+            Op::Rename {
+                dot,
+                clock,
+                old,
+                new,
+            } => {
+                shrunk_ops.push(Op::Add {
+                    members: vec![new.clone()],
+                    dot: dot.clone(),
+                });
+                shrunk_ops.push(Op::Rm {
+                    members: vec![old.clone()],
+                    clock: clock.clone(),
+                });
+            }
         }
 
         Box::new(shrunk_ops.into_iter())
@@ -434,6 +1683,12 @@ impl<M: Debug, A: Ord + Hash + Debug> Debug for Op<M, A> {
         match self {
             Op::Add { dot, members } => write!(f, "Add({:?}, {:?})", dot, members),
             Op::Rm { clock, members } => write!(f, "Rm({:?}, {:?})", clock, members),
+            Op::Rename {
+                dot,
+                clock,
+                old,
+                new,
+            } => write!(f, "Rename({:?}, {:?}, {:?} -> {:?})", dot, clock, old, new),
         }
     }
 }
@@ -1,8 +1,28 @@
-use num_bigint::BigInt;
+use num_bigint::{BigInt, BigUint};
+use num_traits::ToPrimitive;
+#[cfg(feature = "json")]
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::cmp;
+use std::fmt::{self, Debug, Display};
+#[cfg(feature = "json")]
+use std::io::Read;
+use std::time::Instant;
 
-use crate::traits::{CmRDT, CvRDT, ResetRemove};
+use std::collections::hash_map::RandomState;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::causal::CausalOp;
+#[cfg(feature = "json")]
+use crate::codec;
+use crate::gcounter::CounterStore;
+use crate::summary::CrdtSummary;
+use crate::tagged::Tagged;
+#[cfg(feature = "json")]
+use crate::traits::CanonicalBytes;
+use crate::traits::{CmRDT, Compact, CvRDT, CvRDTIdentity, ResetRemove};
+#[cfg(feature = "json")]
+use crate::CrdtError;
 use crate::{Dot, GCounter, VClock};
 
 /// `PNCounter` allows the counter to be both incremented and decremented
@@ -12,6 +32,9 @@ use crate::{Dot, GCounter, VClock};
 /// Merge is implemented by merging the internal P and N counters.
 /// The value of the counter is P minus N.
 ///
+/// Like `GCounter`, the P and N counts are kept in a `VClock` by default,
+/// see `HashPNCounter` for the `HashMap` backed variant.
+///
 /// # Examples
 ///
 /// ```
@@ -26,13 +49,22 @@ use crate::{Dot, GCounter, VClock};
 /// assert_eq!(a.read(), 2.into());
 /// ```
 #[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
-pub struct PNCounter<A: Ord> {
-    p: GCounter<A>,
-    n: GCounter<A>,
+pub struct PNCounter<A: Ord, S = VClock<A>> {
+    p: GCounter<A, S>,
+    n: GCounter<A, S>,
 }
 
+/// A `PNCounter` keeping its counts in `HashMap`s with the hasher `S`.
+pub type HashPNCounter<A, S = RandomState> = PNCounter<A, HashMap<A, u64, S>>;
+
+/// A `PNCounter` with its own `Tag` type, whose ops can't be applied to
+/// counters with another tag, see `Tagged`.
+pub type TaggedPNCounter<A, Tag> = Tagged<PNCounter<A>, Tag>;
+
 /// The Direction of an Op.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// `Pos` is ordered before `Neg`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Dir {
     /// signals that the op increments the counter
     Pos,
@@ -42,7 +74,10 @@ pub enum Dir {
 
 /// An Op which is produced through from mutating the counter
 /// Ship these ops to other replicas to have them sync up.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Ops are totally ordered by actor, then counter, then direction, giving
+/// a canonical order for op logs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Op<A: Ord> {
     /// The witnessing dot for this op
     pub dot: Dot<A>,
@@ -50,7 +85,21 @@ pub struct Op<A: Ord> {
     pub dir: Dir,
 }
 
-impl<A: Ord> Default for PNCounter<A> {
+impl<A: Ord> PartialOrd for Op<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Ord> Ord for Op<A> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.dot
+            .canonical_cmp(&other.dot)
+            .then_with(|| self.dir.cmp(&other.dir))
+    }
+}
+
+impl<A: Ord, S: CounterStore<A>> Default for PNCounter<A, S> {
     fn default() -> Self {
         Self {
             p: Default::default(),
@@ -59,9 +108,25 @@ impl<A: Ord> Default for PNCounter<A> {
     }
 }
 
-impl<A: Ord + Clone + Debug> CmRDT for PNCounter<A> {
+/// Promote a grow-only counter, its counts become the increments.
+impl<A: Ord, S: CounterStore<A>> From<GCounter<A, S>> for PNCounter<A, S> {
+    fn from(p: GCounter<A, S>) -> Self {
+        Self {
+            p,
+            n: Default::default(),
+        }
+    }
+}
+
+impl<A: Ord + Clone, S: CounterStore<A>> Display for PNCounter<A, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.read())
+    }
+}
+
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> CmRDT for PNCounter<A, S> {
     type Op = Op<A>;
-    type Validation = <GCounter<A> as CmRDT>::Validation;
+    type Validation = <GCounter<A, S> as CmRDT>::Validation;
 
     fn validate_op(&self, op: &Self::Op) -> Result<(), Self::Validation> {
         match op {
@@ -78,8 +143,8 @@ impl<A: Ord + Clone + Debug> CmRDT for PNCounter<A> {
     }
 }
 
-impl<A: Ord + Clone + Debug> CvRDT for PNCounter<A> {
-    type Validation = <GCounter<A> as CvRDT>::Validation;
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> CvRDT for PNCounter<A, S> {
+    type Validation = <GCounter<A, S> as CvRDT>::Validation;
 
     fn validate_merge(&self, other: &Self) -> Result<(), Self::Validation> {
         self.p.validate_merge(&other.p)?;
@@ -92,10 +157,55 @@ impl<A: Ord + Clone + Debug> CvRDT for PNCounter<A> {
     }
 }
 
-impl<A: Ord> ResetRemove<A> for PNCounter<A> {
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> std::iter::FromIterator<Op<A>>
+    for PNCounter<A, S>
+{
+    /// Rebuild a counter from a log of ops.
+    ///
+    /// Ops are idempotent so duplicated or reordered entries in the log are harmless.
+    fn from_iter<I: IntoIterator<Item = Op<A>>>(iter: I) -> Self {
+        let mut counter = PNCounter::default();
+        for op in iter {
+            counter.apply(op);
+        }
+        counter
+    }
+}
+
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> std::iter::Sum for PNCounter<A, S> {
+    /// Merges all the counters, starting from the identity.
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        let mut total = PNCounter::identity();
+        for counter in iter {
+            total.merge(counter);
+        }
+        total
+    }
+}
+
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> CvRDTIdentity for PNCounter<A, S> {
+    /// The counter with empty P and N halves.
+    fn identity() -> Self {
+        Default::default()
+    }
+}
+
+impl<A: Ord, S: CounterStore<A>> ResetRemove<A> for PNCounter<A, S> {
     fn reset_remove(&mut self, clock: &VClock<A>) {
-        self.p.reset_remove(&clock);
-        self.n.reset_remove(&clock);
+        self.p.reset_remove(clock);
+        self.n.reset_remove(clock);
+    }
+}
+
+impl<A: Ord, S: CounterStore<A>> Compact<A> for PNCounter<A, S> {
+    /// A `PNCounter` holds no history, every count is part of its value.
+    fn compact(&mut self, _stable: &VClock<A>) {}
+}
+
+#[cfg(feature = "json")]
+impl<A: Ord + Clone + Serialize, S: CounterStore<A>> CanonicalBytes for PNCounter<A, S> {
+    fn canonical_bytes(&self) -> Vec<u8> {
+        PNCounter::canonical_bytes(self)
     }
 }
 
@@ -104,6 +214,111 @@ impl<A: Ord + Clone> PNCounter<A> {
     pub fn new() -> Self {
         Default::default()
     }
+}
+
+/// `FlatPNCounter` wraps a `PNCounter` to serialize it as a single object
+/// holding its value alongside the full state, for embedding with
+/// `#[serde(flatten)]` into a larger document.
+///
+/// Serializes as `{ "value": .., "inc": .., "dec": .. }`. The `value`,
+/// saturated to the `i64` range, is there for readers of the document. It's
+/// ignored when deserializing, the counter is rebuilt from the `inc` and
+/// `dec` clocks.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{pncounter::FlatPNCounter, CmRDT, PNCounter};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Post {
+///     title: String,
+///     #[serde(flatten)]
+///     likes: FlatPNCounter<String>,
+/// }
+///
+/// let mut likes = PNCounter::new();
+/// likes.apply(likes.inc("A".to_string()));
+/// let post = Post { title: "hello".into(), likes: likes.into() };
+///
+/// let json = serde_json::to_value(&post).unwrap();
+/// assert_eq!(json["title"], "hello");
+/// assert_eq!(json["value"], 1);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FlatPNCounter<A: Ord>(pub PNCounter<A>);
+
+#[derive(Serialize)]
+struct FlatRef<'a, A: Ord> {
+    value: i64,
+    inc: &'a VClock<A>,
+    dec: &'a VClock<A>,
+}
+
+#[derive(Deserialize)]
+struct Flat<A: Ord> {
+    inc: VClock<A>,
+    dec: VClock<A>,
+}
+
+/// Convert `value` to an `i64`, saturating at the bounds of the range.
+fn saturating_i64(value: &BigInt) -> i64 {
+    value.to_i64().unwrap_or_else(|| {
+        if *value > BigInt::from(0) {
+            i64::MAX
+        } else {
+            i64::MIN
+        }
+    })
+}
+
+impl<A: Ord + Clone + Serialize> Serialize for FlatPNCounter<A> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        FlatRef {
+            value: saturating_i64(&self.0.read()),
+            inc: &self.0.p.inner,
+            dec: &self.0.n.inner,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, A: Ord + Clone + Deserialize<'de>> Deserialize<'de> for FlatPNCounter<A> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Flat { inc, dec } = Flat::deserialize(deserializer)?;
+        let mut counter = PNCounter::new();
+        counter.p.inner = inc;
+        counter.n.inner = dec;
+        Ok(FlatPNCounter(counter))
+    }
+}
+
+impl<A: Ord> From<PNCounter<A>> for FlatPNCounter<A> {
+    fn from(counter: PNCounter<A>) -> Self {
+        FlatPNCounter(counter)
+    }
+}
+
+impl<A: Ord> From<FlatPNCounter<A>> for PNCounter<A> {
+    fn from(flat: FlatPNCounter<A>) -> Self {
+        flat.0
+    }
+}
+
+impl<A: Ord + Clone, S: CounterStore<A>> PNCounter<A, S> {
+    /// Returns a counter that has already applied every dot of the `p` and
+    /// `n` baselines, see `GCounter::init_at`.
+    ///
+    /// A `PNCounter` tracks increments and decrements in separate clocks, so
+    /// it takes a baseline for each. The value starts at the sum of `p`
+    /// minus the sum of `n`.
+    pub fn init_at(p: VClock<A>, n: VClock<A>) -> Self {
+        Self {
+            p: GCounter::init_at(p),
+            n: GCounter::init_at(n),
+        }
+    }
 
     /// Generate an Op to increment the counter.
     pub fn inc(&self, actor: A) -> Op<A> {
@@ -121,6 +336,33 @@ impl<A: Ord + Clone> PNCounter<A> {
         }
     }
 
+    /// Generate an Op to increment the counter, tagged with the clock `deps`
+    /// it causally depends on, see `CausalOp`.
+    pub fn inc_with_deps(&self, actor: A, deps: VClock<A>) -> CausalOp<Op<A>, A> {
+        CausalOp::new(self.inc(actor), deps)
+    }
+
+    /// Generate an Op to decrement the counter, tagged with the clock `deps`
+    /// it causally depends on, see `CausalOp`.
+    pub fn dec_with_deps(&self, actor: A, deps: VClock<A>) -> CausalOp<Op<A>, A> {
+        CausalOp::new(self.dec(actor), deps)
+    }
+
+    /// Generate an Op to decrement the counter, or `None` if the counter is
+    /// not above zero on this replica.
+    ///
+    /// This only guards against this replica decrementing past zero.
+    /// Replicas decrementing concurrently each see the value before the
+    /// others' decrements, so after merging the counter can still go
+    /// negative.
+    pub fn checked_dec(&self, actor: A) -> Option<Op<A>> {
+        if self.read() > BigInt::from(0) {
+            Some(self.dec(actor))
+        } else {
+            None
+        }
+    }
+
     /// Generate an Op to increment the counter by a number of steps.
     pub fn inc_many(&self, actor: A, steps: u64) -> Op<A> {
         Op {
@@ -137,12 +379,350 @@ impl<A: Ord + Clone> PNCounter<A> {
         }
     }
 
+    /// Generate an Op to increment the counter by `amount` in a single op.
+    /// The actor's count saturates at `u64::MAX` rather than overflowing.
+    pub fn inc_by(&self, actor: A, amount: u64) -> Op<A> {
+        Op {
+            dot: self.p.inc_by(actor, amount),
+            dir: Dir::Pos,
+        }
+    }
+
+    /// Generate an Op to decrement the counter by `amount` in a single op.
+    /// The actor's count saturates at `u64::MAX` rather than overflowing.
+    pub fn dec_by(&self, actor: A, amount: u64) -> Op<A> {
+        Op {
+            dot: self.n.inc_by(actor, amount),
+            dir: Dir::Neg,
+        }
+    }
+
+    /// Apply the op, returning true iff it raised the actor's count, see
+    /// `GCounter::apply_detect`.
+    pub fn apply_detect(&mut self, op: &Op<A>) -> bool {
+        match op.dir {
+            Dir::Pos => self.p.apply_detect(&op.dot),
+            Dir::Neg => self.n.apply_detect(&op.dot),
+        }
+    }
+
     /// Return the current value of this counter (P-N).
     pub fn read(&self) -> BigInt {
         let p: BigInt = self.p.read().into();
         let n: BigInt = self.n.read().into();
         p - n
     }
+
+    /// Returns a canonical encoding of the counter: the JSON pair of the
+    /// canonical counts of its increments and decrements, see
+    /// `GCounter::canonical_bytes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an actor fails to serialize.
+    #[cfg(feature = "json")]
+    pub fn canonical_bytes(&self) -> Vec<u8>
+    where
+        A: Serialize,
+    {
+        let counts = (self.p.canonical_counts(), self.n.canonical_counts());
+        serde_json::to_vec(&counts).expect("failed to serialize actor")
+    }
+
+    /// Returns the value together with the number of actors that have
+    /// incremented or decremented the counter and the number of ops seen.
+    pub fn summary(&self) -> CrdtSummary<BigInt> {
+        let mut actors = BTreeSet::new();
+        let mut causal_length = 0u64;
+        for dot in self.p.inner.dots().chain(self.n.inner.dots()) {
+            if dot.counter > 0 {
+                actors.insert(dot.actor);
+                causal_length = causal_length.saturating_add(dot.counter);
+            }
+        }
+        CrdtSummary {
+            value: self.read(),
+            actor_count: actors.len(),
+            causal_length,
+        }
+    }
+
+    /// Move the old actor's counts to the new actor, e.g. after a replica
+    /// was given a new id, see `GCounter::rekey_actor`.
+    ///
+    /// This is not a CRDT operation, it must be coordinated across replicas
+    /// to stay convergent.
+    pub fn rekey_actor(&mut self, old: &A, new: A) {
+        self.p.rekey_actor(old, new.clone());
+        self.n.rekey_actor(old, new);
+    }
+
+    /// Returns how much the (P, N) halves grew since the `prior` snapshot,
+    /// computed per actor, e.g. to flag decrements of a counter that should
+    /// only grow.
+    ///
+    /// `prior` must be a causal ancestor of self for the gains to mean
+    /// anything. An actor whose count in `prior` is ahead of ours contributes
+    /// no gain rather than a negative one, and the totals saturate at
+    /// `u64::MAX`.
+    pub fn gained_since(&self, prior: &Self) -> (u64, u64) {
+        fn gained<A: Ord, S: CounterStore<A>>(now: &GCounter<A, S>, prior: &GCounter<A, S>) -> u64 {
+            now.inner.dots().fold(0u64, |total, dot| {
+                let gain = dot.counter.saturating_sub(prior.inner.count(dot.actor));
+                total.saturating_add(gain)
+            })
+        }
+        (gained(&self.p, &prior.p), gained(&self.n, &prior.n))
+    }
+
+    /// Merge each of the given counters into this one.
+    ///
+    /// Merge is associative and commutative so the order doesn't matter.
+    pub fn merge_all(&mut self, others: &[Self]) {
+        let owned = |dot: Dot<&A>| Dot::new(dot.actor.clone(), dot.counter);
+        self.p.observe_all(
+            others
+                .iter()
+                .flat_map(|other| other.p.inner.dots())
+                .map(owned),
+        );
+        self.n.observe_all(
+            others
+                .iter()
+                .flat_map(|other| other.n.inner.dots())
+                .map(owned),
+        );
+    }
+
+    /// Merge `other` into self, limiting each actor's P and N counts to `per_actor_cap`.
+    ///
+    /// This bounds how far a single compromised replica can swing the value.
+    /// Note that it is not a plain merge: counts of self above the cap are
+    /// lowered too, so replicas only converge if all of them merge with the
+    /// same cap, in effect defining a bounded variant of the counter.
+    pub fn merge_clamped(&mut self, other: &Self, per_actor_cap: u64) {
+        let clamped = |ours: &GCounter<A, S>, theirs: &GCounter<A, S>| {
+            let mut counter = GCounter::<A, S>::default();
+            for dot in ours.inner.dots().chain(theirs.inner.dots()) {
+                let capped = cmp::min(dot.counter, per_actor_cap);
+                counter.inner.observe(Dot::new(dot.actor.clone(), capped));
+            }
+            counter
+        };
+        self.p = clamped(&self.p, &other.p);
+        self.n = clamped(&self.n, &other.n);
+    }
+
+    /// Return the value this counter had as of the given clock.
+    ///
+    /// Only contributions witnessed by `clock` are counted, that is, each
+    /// actor's increments and decrements are clamped to that actor's counter
+    /// in `clock`. The value saturates at the bounds of the `i64` range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crdts::{CmRDT, Dot, PNCounter, VClock};
+    ///
+    /// let mut a = PNCounter::new();
+    /// a.apply(a.inc_many("A", 3));
+    /// a.apply(a.dec("A"));
+    ///
+    /// let clock = VClock::from(Dot::new("A", 2));
+    /// assert_eq!(a.value_at(&clock), 1);
+    /// ```
+    pub fn value_at(&self, clock: &VClock<A>) -> i64 {
+        let witnessed = |counter: &GCounter<A, S>| -> BigInt {
+            counter
+                .inner
+                .dots()
+                .map(|dot| cmp::min(dot.counter, clock.get(dot.actor)))
+                .sum::<BigUint>()
+                .into()
+        };
+        saturating_i64(&(witnessed(&self.p) - witnessed(&self.n)))
+    }
+}
+
+#[cfg(feature = "json")]
+impl<A: Ord + Clone + Debug + DeserializeOwned, S: CounterStore<A>> PNCounter<A, S> {
+    /// Apply the ops framed by `codec::write_delimited` in `reader`, one at
+    /// a time, returning the number of ops applied.
+    ///
+    /// See `GCounter::apply_stream`.
+    pub fn apply_stream<R: Read>(&mut self, reader: R) -> Result<usize, CrdtError<A>> {
+        codec::apply_stream(self, reader)
+    }
+}
+
+/// `WeightedPNCounter` is a `PNCounter` where each actor contributes at
+/// most its quota, e.g. for bounded voting: an actor's increments, and its
+/// decrements, each count up to the actor's cap. Actors without a cap can't
+/// contribute at all.
+///
+/// The caps are part of the counter, every replica must be built with the
+/// same caps for them to converge. Ops, merges and deserialized states
+/// that would take an actor past its cap are clamped to it.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{pncounter::WeightedPNCounter, CmRDT};
+///
+/// let mut votes = WeightedPNCounter::new(vec![("A", 2), ("B", 1)].into_iter().collect());
+/// votes.apply(votes.inc("A").unwrap());
+/// votes.apply(votes.inc("A").unwrap());
+/// assert_eq!(votes.inc("A"), None);
+/// assert_eq!(votes.read(), 2.into());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "WeightedParts<A>")]
+pub struct WeightedPNCounter<A: Ord> {
+    counter: PNCounter<A>,
+    #[serde(with = "crate::vclock::dots_serde")]
+    caps: BTreeMap<A, u64>,
+}
+
+/// The serialized form of a `WeightedPNCounter`, clamped to its caps when
+/// converted back.
+#[derive(Deserialize)]
+struct WeightedParts<A: Ord> {
+    counter: PNCounter<A>,
+    #[serde(with = "crate::vclock::dots_serde")]
+    caps: BTreeMap<A, u64>,
+}
+
+impl<A: Ord> From<WeightedParts<A>> for WeightedPNCounter<A> {
+    fn from(parts: WeightedParts<A>) -> Self {
+        let WeightedParts { mut counter, caps } = parts;
+        clamp(&mut counter.p, &caps);
+        clamp(&mut counter.n, &caps);
+        Self { counter, caps }
+    }
+}
+
+/// Lower the counts past their actor's cap to the cap.
+fn clamp<A: Ord>(counts: &mut GCounter<A>, caps: &BTreeMap<A, u64>) {
+    counts.inner.dots.retain(|actor, count| {
+        *count = cmp::min(*count, cap_of(caps, actor));
+        *count > 0
+    });
+}
+
+fn cap_of<A: Ord>(caps: &BTreeMap<A, u64>, actor: &A) -> u64 {
+    caps.get(actor).cloned().unwrap_or(0)
+}
+
+/// Observe the counts of `theirs`, clamped to the caps.
+fn observe_clamped<A: Ord>(ours: &mut GCounter<A>, theirs: GCounter<A>, caps: &BTreeMap<A, u64>) {
+    for dot in theirs.inner.into_iter() {
+        let capped = cmp::min(dot.counter, cap_of(caps, &dot.actor));
+        ours.inner.observe(Dot::new(dot.actor, capped));
+    }
+}
+
+impl<A: Ord + Clone + Debug> CmRDT for WeightedPNCounter<A> {
+    type Op = Op<A>;
+    type Validation = <PNCounter<A> as CmRDT>::Validation;
+
+    fn validate_op(&self, op: &Self::Op) -> Result<(), Self::Validation> {
+        self.counter.validate_op(op)
+    }
+
+    fn apply(&mut self, mut op: Self::Op) {
+        op.dot.counter = cmp::min(op.dot.counter, self.cap(&op.dot.actor));
+        self.counter.apply(op)
+    }
+}
+
+impl<A: Ord + Clone + Debug> CvRDT for WeightedPNCounter<A> {
+    type Validation = <PNCounter<A> as CvRDT>::Validation;
+
+    fn validate_merge(&self, other: &Self) -> Result<(), Self::Validation> {
+        self.counter.validate_merge(&other.counter)
+    }
+
+    /// Merge the counts of `other`, clamped to our caps. The caps of
+    /// `other` are assumed to be ours and aren't merged.
+    fn merge(&mut self, other: Self) {
+        observe_clamped(&mut self.counter.p, other.counter.p, &self.caps);
+        observe_clamped(&mut self.counter.n, other.counter.n, &self.caps);
+    }
+}
+
+impl<A: Ord + Clone> WeightedPNCounter<A> {
+    /// Returns a new counter where each actor contributes at most its cap.
+    pub fn new(caps: BTreeMap<A, u64>) -> Self {
+        Self {
+            counter: PNCounter::new(),
+            caps,
+        }
+    }
+
+    /// Returns the cap of this actor, 0 if it has none.
+    pub fn cap(&self, actor: &A) -> u64 {
+        cap_of(&self.caps, actor)
+    }
+
+    /// Generate an op to increment the counter, or `None` if the actor's
+    /// increments have reached its cap.
+    pub fn inc(&self, actor: A) -> Option<Op<A>> {
+        if self.counter.p.inner.get(&actor) >= self.cap(&actor) {
+            return None;
+        }
+        Some(self.counter.inc(actor))
+    }
+
+    /// Generate an op to decrement the counter, or `None` if the actor's
+    /// decrements have reached its cap.
+    pub fn dec(&self, actor: A) -> Option<Op<A>> {
+        if self.counter.n.inner.get(&actor) >= self.cap(&actor) {
+            return None;
+        }
+        Some(self.counter.dec(actor))
+    }
+
+    /// Return the current value of this counter (P-N).
+    pub fn read(&self) -> BigInt {
+        self.counter.read()
+    }
+
+    /// Returns the underlying counter.
+    pub fn counter(&self) -> &PNCounter<A> {
+        &self.counter
+    }
+}
+
+/// Fold each run of consecutive ops of the same actor and direction into
+/// a single op, see `gcounter::compact_ops`.
+pub fn compact_ops<A: Ord>(ops: Vec<Op<A>>) -> Vec<Op<A>> {
+    let mut compacted: Vec<Op<A>> = Vec::with_capacity(ops.len());
+    for op in ops {
+        match compacted.last_mut() {
+            Some(last) if last.dir == op.dir && last.dot.actor == op.dot.actor => {
+                last.dot.counter = cmp::max(last.dot.counter, op.dot.counter)
+            }
+            _ => compacted.push(op),
+        }
+    }
+    compacted
+}
+
+/// Returns the per-second rate of change between two timestamped snapshots
+/// of a counter.
+///
+/// Returns 0.0 if no time has elapsed between the snapshots, or if `curr`
+/// was taken before `prev`.
+pub fn rate<A: Ord + Clone, S: CounterStore<A>>(
+    prev: (&PNCounter<A, S>, Instant),
+    curr: (&PNCounter<A, S>, Instant),
+) -> f64 {
+    let elapsed = match curr.1.checked_duration_since(prev.1) {
+        Some(elapsed) if elapsed.as_secs_f64() > 0.0 => elapsed.as_secs_f64(),
+        _ => return 0.0,
+    };
+    let delta = (curr.0.read() - prev.0.read()).to_f64().unwrap_or(0.0);
+    delta / elapsed
 }
 
 #[cfg(test)]
@@ -163,6 +743,134 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_flat_counter_round_trips_when_flattened() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Doc {
+            name: String,
+            #[serde(flatten)]
+            votes: FlatPNCounter<u8>,
+        }
+
+        let mut votes = PNCounter::new();
+        votes.apply(votes.inc_by(1, 3));
+        votes.apply(votes.dec(2));
+        let doc = Doc {
+            name: "poll".into(),
+            votes: votes.clone().into(),
+        };
+
+        let json = serde_json::to_value(&doc).unwrap();
+        assert_eq!(json["name"], "poll");
+        assert_eq!(json["value"], 2);
+
+        let decoded: Doc = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, doc);
+        assert_eq!(PNCounter::from(decoded.votes), votes);
+    }
+
+    #[test]
+    fn test_init_at_baseline() {
+        let p: VClock<_> = vec![Dot::new('A', 5)].into_iter().collect();
+        let n: VClock<_> = vec![Dot::new('A', 2), Dot::new('B', 1)]
+            .into_iter()
+            .collect();
+        let mut a: PNCounter<_> = PNCounter::init_at(p, n);
+        assert_eq!(a.read(), 2.into());
+
+        a.apply(Op {
+            dot: Dot::new('A', 2),
+            dir: Dir::Neg,
+        });
+        assert_eq!(a.read(), 2.into());
+
+        a.apply(a.dec('B'));
+        assert_eq!(a.read(), 1.into());
+    }
+
+    #[test]
+    fn test_checked_dec() {
+        let mut a = PNCounter::new();
+        assert_eq!(a.checked_dec('A'), None);
+
+        a.apply(a.inc('A'));
+        let op = a.checked_dec('A').unwrap();
+        a.apply(op);
+        assert_eq!(a.read(), 0.into());
+        assert_eq!(a.checked_dec('A'), None);
+
+        // concurrent decrements can still take the merged value below zero
+        let mut b = a.clone();
+        a.apply(a.inc('A'));
+        b.apply(b.inc('B'));
+        let mut c = a.clone();
+        c.merge(b.clone());
+        a.apply(a.checked_dec('A').unwrap());
+        c.apply(c.checked_dec('C').unwrap());
+        c.apply(c.checked_dec('C').unwrap());
+        c.merge(a);
+        assert_eq!(c.read(), (-1).into());
+    }
+
+    #[test]
+    fn test_gained_since() {
+        let mut a = PNCounter::new();
+        a.apply(a.inc_by('A', 3));
+        a.apply(a.dec('B'));
+        let prior = a.clone();
+
+        a.apply(a.inc_by('A', 2));
+        a.apply(a.inc('C'));
+        a.apply(a.dec_by('B', 4));
+        assert_eq!(a.gained_since(&prior), (3, 4));
+        assert_eq!(a.gained_since(&a), (0, 0));
+
+        // a prior that isn't an ancestor gains nothing for the actors it's ahead on
+        assert_eq!(prior.gained_since(&a), (0, 0));
+    }
+
+    #[test]
+    fn test_rekey_actor() {
+        let mut a = PNCounter::new();
+        a.apply(a.inc_by('A', 5));
+        a.apply(a.dec_by('A', 2));
+        a.apply(a.dec('B'));
+
+        a.rekey_actor(&'A', 'C');
+        assert_eq!(a.read(), 2.into());
+        assert_eq!(a.p.inc('A'), Dot::new('A', 1));
+        assert_eq!(a.n.inc('A'), Dot::new('A', 1));
+
+        // the new actor keeps counting from the moved counts
+        a.apply(a.inc('C'));
+        assert_eq!(a.read(), 3.into());
+    }
+
+    #[test]
+    fn test_inc_by_and_dec_by() {
+        let mut a = PNCounter::new();
+        let op = a.inc_by('A', 5);
+        a.apply(op.clone());
+        assert_eq!(a.read(), 5.into());
+
+        // redelivering the same op is a no-op
+        a.apply(op);
+        assert_eq!(a.read(), 5.into());
+
+        a.apply(a.dec_by('A', 7));
+        assert_eq!(a.read(), (-2).into());
+    }
+
+    #[test]
+    fn test_inc_by_saturates() {
+        let mut a = PNCounter::new();
+        a.apply(a.inc_by('A', u64::MAX - 1));
+        let op = a.inc_by('A', 5);
+        assert_eq!(op.dot.counter, u64::MAX);
+        a.apply(op);
+        assert_eq!(a.read(), u64::MAX.into());
+    }
+
     quickcheck! {
         fn prop_merge_converges(op_prims: Vec<(u8, u64, bool)>) -> bool {
             let ops: Vec<Op<u8>> = op_prims.into_iter().map(build_op).collect();
@@ -194,6 +902,15 @@ mod test {
             }
             results.len() == 1
         }
+
+        fn prop_identity_is_merge_neutral(op_prims: Vec<(u8, u64, bool)>) -> bool {
+            let mut a = PNCounter::new();
+            op_prims.into_iter().map(build_op).for_each(|op| a.apply(op));
+
+            let mut merged = a.clone();
+            merged.merge(PNCounter::identity());
+            merged == a
+        }
     }
 
     #[test]
@@ -233,4 +950,316 @@ mod test {
         a.apply(a.inc_many("A", 1));
         assert_eq!(a.read(), (1 + steps).into());
     }
+
+    #[test]
+    fn test_from_iter_matches_apply() {
+        let mut a = PNCounter::new();
+        let mut ops = Vec::new();
+        for op_gen in &[
+            PNCounter::inc,
+            PNCounter::inc,
+            PNCounter::dec,
+            PNCounter::inc,
+        ] {
+            let op = op_gen(&a, "A");
+            a.apply(op.clone());
+            ops.push(op);
+        }
+        let op = a.dec("B");
+        a.apply(op.clone());
+        ops.push(op);
+
+        let rebuilt: PNCounter<_> = ops.iter().cloned().collect();
+        assert_eq!(rebuilt, a);
+
+        // duplicates and reordering in the log are harmless
+        let replayed: PNCounter<_> = ops.iter().rev().chain(ops.iter()).cloned().collect();
+        assert_eq!(replayed, a);
+    }
+
+    #[test]
+    fn test_hash_backend_converges_with_default() {
+        let mut ordered = PNCounter::new();
+        let mut hashed: HashPNCounter<u8> = Default::default();
+        for op_prim in (0..200u64).map(|i| ((i % 7) as u8, i, i % 3 != 0)) {
+            ordered.apply(build_op(op_prim));
+            hashed.apply(build_op(op_prim));
+        }
+        assert_eq!(ordered.read(), hashed.read());
+    }
+
+    #[test]
+    fn test_sum_and_merge_all_match_manual_merge() {
+        let mut a = PNCounter::new();
+        a.apply(a.inc_many("A", 3));
+        let mut b = a.clone();
+        b.apply(b.dec("B"));
+        let mut c = PNCounter::new();
+        c.apply(c.inc_many("C", 2));
+        c.apply(c.dec_many("A", 2));
+
+        let mut manual = a.clone();
+        manual.merge(b.clone());
+        manual.merge(c.clone());
+
+        let summed: PNCounter<_> = vec![a.clone(), b.clone(), c.clone()].into_iter().sum();
+        assert_eq!(summed, manual);
+        assert_eq!(summed.read(), 2.into());
+
+        let mut merged_all = PNCounter::new();
+        merged_all.merge_all(&[a, b, c]);
+        assert_eq!(merged_all, manual);
+
+        let empty: PNCounter<&str> = Vec::new().into_iter().sum();
+        assert_eq!(empty, PNCounter::new());
+    }
+
+    #[test]
+    fn test_merge_clamped() {
+        let mut a = PNCounter::new();
+        a.apply(a.inc_many("A", 3));
+        a.apply(a.dec("A"));
+
+        let mut b = a.clone();
+        b.apply(b.inc_many("B", 1_000));
+        b.apply(b.dec_many("C", 50));
+        b.apply(b.inc("A"));
+
+        a.merge_clamped(&b, 10);
+        assert_eq!(a.p.inner.get(&"A"), 4);
+        assert_eq!(a.p.inner.get(&"B"), 10);
+        assert_eq!(a.n.inner.get(&"C"), 10);
+        assert_eq!(a.read(), (4 + 10 - 1 - 10).into());
+
+        // merging with the same cap is idempotent
+        let before = a.clone();
+        a.merge_clamped(&b, 10);
+        assert_eq!(a, before);
+    }
+
+    #[test]
+    fn test_rate() {
+        use std::time::Duration;
+
+        let mut a = PNCounter::new();
+        a.apply(a.inc_many("A", 10));
+        let prev = a.clone();
+        a.apply(a.inc_many("A", 50));
+        a.apply(a.dec_many("B", 20));
+
+        let start = Instant::now();
+        let end = start + Duration::from_millis(2_500);
+        assert_eq!(rate((&prev, start), (&a, end)), 12.0);
+        assert_eq!(
+            rate((&a, end), (&prev, start + Duration::from_secs(5))),
+            -12.0
+        );
+        assert_eq!(rate((&prev, start), (&a, start)), 0.0);
+        assert_eq!(rate((&prev, end), (&a, start)), 0.0);
+    }
+
+    #[test]
+    fn test_ops_sort_canonically() {
+        let op = |actor, counter, dir| Op {
+            dot: Dot::new(actor, counter),
+            dir,
+        };
+        let canonical = vec![
+            op("A", 1, Dir::Pos),
+            op("A", 1, Dir::Neg),
+            op("A", 2, Dir::Pos),
+            op("B", 1, Dir::Neg),
+            op("B", 3, Dir::Pos),
+        ];
+        let mut shuffled = vec![
+            canonical[3].clone(),
+            canonical[1].clone(),
+            canonical[4].clone(),
+            canonical[0].clone(),
+            canonical[2].clone(),
+        ];
+        shuffled.sort();
+        assert_eq!(shuffled, canonical);
+    }
+
+    #[test]
+    fn test_value_at() {
+        let mut a = PNCounter::new();
+        a.apply(a.inc("A"));
+        a.apply(a.inc("A"));
+        a.apply(a.inc("B"));
+        a.apply(a.dec("A"));
+        a.apply(a.inc("A"));
+
+        let mut clock = VClock::new();
+        clock.apply(Dot::new("A", 2));
+        assert_eq!(a.value_at(&clock), 1);
+
+        clock.apply(Dot::new("B", 1));
+        assert_eq!(a.value_at(&clock), 2);
+
+        clock.apply(Dot::new("A", 3));
+        assert_eq!(BigInt::from(a.value_at(&clock)), a.read());
+        assert_eq!(a.value_at(&VClock::new()), 0);
+
+        let mut b = PNCounter::new();
+        b.apply(b.inc_by("A", u64::MAX));
+        b.apply(b.inc_by("B", u64::MAX));
+        let clock = b.p.inner.clone();
+        assert_eq!(b.value_at(&clock), i64::MAX);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_apply_stream() {
+        let mut writer = PNCounter::new();
+        let mut stream = Vec::new();
+        for op in [writer.inc_by(1u8, 5), writer.dec(2), writer.dec_by(1, 3)] {
+            codec::write_delimited(&mut stream, &op).unwrap();
+            writer.apply(op);
+        }
+
+        let mut a: PNCounter<u8> = PNCounter::new();
+        assert_eq!(a.apply_stream(stream.as_slice()), Ok(3));
+        assert_eq!(a.read(), 1.into());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_apply_stream_rejects_malformed_op() {
+        let mut stream = Vec::new();
+        let mut a = PNCounter::new();
+        codec::write_delimited(&mut stream, &a.inc(1u8)).unwrap();
+        codec::write_delimited(&mut stream, &"not an op").unwrap();
+
+        assert_eq!(
+            a.apply_stream(stream.as_slice()),
+            Err(CrdtError::MalformedOp { index: 1 })
+        );
+        assert_eq!(a.read(), 1.into());
+    }
+
+    #[test]
+    fn test_apply_detect() {
+        let mut a = PNCounter::new();
+        let inc = a.inc(1u8);
+        let dec = a.dec(1);
+        assert!(a.apply_detect(&inc));
+        assert!(!a.apply_detect(&inc));
+        assert!(a.apply_detect(&dec));
+        assert!(!a.apply_detect(&dec));
+        assert_eq!(a.read(), 0.into());
+    }
+
+    #[test]
+    fn test_compact_ops() {
+        let mut a = PNCounter::new();
+        let mut ops = Vec::new();
+        for _ in 0..5 {
+            let op = a.inc(1u8);
+            a.apply(op.clone());
+            ops.push(op);
+        }
+        let op = a.dec(1);
+        a.apply(op.clone());
+        ops.push(op);
+
+        let compacted = compact_ops(ops);
+        assert_eq!(compacted.len(), 2);
+
+        let mut b = PNCounter::new();
+        compacted.into_iter().for_each(|op| b.apply(op));
+        assert_eq!(b, a);
+        assert_eq!(b.read(), 4.into());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_canonical_bytes_ignore_build_order() {
+        let mut a = PNCounter::new();
+        a.apply(a.inc(1u8));
+        a.apply(a.dec(2));
+        let mut b = PNCounter::new();
+        b.apply(b.dec(2u8));
+        b.apply(b.inc(1));
+
+        assert_eq!(a.canonical_bytes(), b.canonical_bytes());
+        b.apply(b.inc(1));
+        assert_ne!(a.canonical_bytes(), b.canonical_bytes());
+    }
+
+    #[test]
+    fn test_weighted_counter_respects_caps() {
+        let caps: BTreeMap<u8, u64> = vec![(1, 2), (2, 3)].into_iter().collect();
+        let mut a = WeightedPNCounter::new(caps.clone());
+        let mut b = WeightedPNCounter::new(caps);
+
+        a.apply(a.inc(1).unwrap());
+        a.apply(a.inc(1).unwrap());
+        assert_eq!(a.inc(1), None);
+        assert_eq!(a.inc(3), None);
+
+        for _ in 0..3 {
+            b.apply(b.inc(2).unwrap());
+        }
+        b.apply(b.dec(1).unwrap());
+        // a peer without caps tries to push actor 2 past its quota
+        let mut rogue = PNCounter::new();
+        rogue.apply(rogue.inc_many(2, 10));
+        b.apply(rogue.inc(2));
+
+        a.merge(b.clone());
+        b.merge(a.clone());
+        assert_eq!(a, b);
+        assert_eq!(a.read(), 4.into());
+        assert_eq!(a.counter().p.inner.get(&2), 3);
+
+        // merges clamp replicas built with larger caps
+        let mut generous = WeightedPNCounter::new(vec![(2, 10)].into_iter().collect());
+        generous.apply(generous.counter().inc_many(2, 10));
+        a.merge(generous);
+        assert_eq!(a.read(), 4.into());
+    }
+
+    #[test]
+    fn test_weighted_counter_deserialize_clamps_to_caps() {
+        let mut generous = WeightedPNCounter::new(vec![(1u8, 10), (2, 10)].into_iter().collect());
+        generous.apply(generous.counter().inc_many(1, 10));
+        generous.apply(generous.counter().dec_many(2, 4));
+        let mut json = serde_json::to_value(&generous).unwrap();
+        json["caps"] = serde_json::json!({ "1": 3 });
+
+        let clamped: WeightedPNCounter<u8> = serde_json::from_value(json).unwrap();
+        assert_eq!(clamped.read(), 3.into());
+        assert_eq!(clamped, {
+            let mut expected = WeightedPNCounter::new(vec![(1, 3)].into_iter().collect());
+            expected.apply(expected.counter().inc_many(1, 3));
+            expected
+        });
+    }
+
+    #[test]
+    fn test_weighted_counter_json_round_trip_with_tuple_actors() {
+        let caps: BTreeMap<(u8, u8), u64> = vec![((1, 0), 2), ((2, 1), 3)].into_iter().collect();
+        let mut a = WeightedPNCounter::new(caps);
+        a.apply(a.inc((1, 0)).unwrap());
+        a.apply(a.dec((2, 1)).unwrap());
+
+        let json = serde_json::to_string(&a).unwrap();
+        let decoded: WeightedPNCounter<(u8, u8)> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, a);
+        assert_eq!(decoded.cap(&(2, 1)), 3);
+    }
+
+    #[test]
+    fn test_from_gcounter() {
+        let mut g = GCounter::new();
+        g.apply(g.inc_many(1u8, 3));
+        g.apply(g.inc(2));
+
+        let mut a = PNCounter::from(g.clone());
+        assert_eq!(a.read(), BigInt::from(g.read()));
+        a.apply(a.dec(1));
+        assert_eq!(a.read(), 3.into());
+    }
 }
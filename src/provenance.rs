@@ -0,0 +1,151 @@
+use crate::checkpoint::digest;
+use crate::{CanonicalBytes, CmRDT, CvRDT};
+
+/// One merge recorded by `Provenance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MergeRecord {
+    /// The digest of the merged in state
+    pub source: u64,
+    /// The digest of the state after the merge
+    pub result: u64,
+}
+
+/// `Provenance` wraps a CRDT and records the digests of every state merged
+/// into it along with the resulting state, so a test whose replicas fail to
+/// converge can dump each replica's trail to find the merge that diverged.
+///
+/// The digests are those recorded by `Checkpoints`, taken over the
+/// state's `CanonicalBytes`.
+///
+/// It is a debugging aid, only available with the `provenance` feature.
+/// Ops are applied as usual and aren't recorded.
+///
+/// ```
+/// use crdts::checkpoint::Checkpoints;
+/// use crdts::provenance::{MergeRecord, Provenance};
+/// use crdts::{CmRDT, CvRDT, GCounter};
+///
+/// let mut a = Provenance::new(GCounter::new());
+/// let mut b = GCounter::new();
+/// b.apply(b.inc("B"));
+///
+/// let mut checkpoints = Checkpoints::new();
+/// checkpoints.record("b", &b);
+/// a.merge(Provenance::new(b));
+/// checkpoints.record("a", a.crdt());
+///
+/// assert_eq!(
+///     a.trail(),
+///     &[MergeRecord {
+///         source: checkpoints.digest("b").unwrap(),
+///         result: checkpoints.digest("a").unwrap(),
+///     }]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Provenance<T> {
+    crdt: T,
+    trail: Vec<MergeRecord>,
+}
+
+impl<T> Provenance<T> {
+    /// Start recording the merges into `crdt`.
+    pub fn new(crdt: T) -> Self {
+        Self {
+            crdt,
+            trail: Vec::new(),
+        }
+    }
+
+    /// The merges recorded so far, oldest first.
+    pub fn trail(&self) -> &[MergeRecord] {
+        &self.trail
+    }
+
+    /// Returns a reference to the CRDT.
+    pub fn crdt(&self) -> &T {
+        &self.crdt
+    }
+
+    /// Unwraps the CRDT, dropping the trail.
+    pub fn into_inner(self) -> T {
+        self.crdt
+    }
+}
+
+/// Replicas are equal if their CRDT's are, whatever their trails.
+impl<T: PartialEq> PartialEq for Provenance<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.crdt == other.crdt
+    }
+}
+
+impl<T: Eq> Eq for Provenance<T> {}
+
+impl<T: CvRDT + CanonicalBytes> CvRDT for Provenance<T> {
+    type Validation = T::Validation;
+
+    fn validate_merge(&self, other: &Self) -> Result<(), Self::Validation> {
+        self.crdt.validate_merge(&other.crdt)
+    }
+
+    /// Merge the other CRDT into ours, recording the merge. The trail of
+    /// `other` is discarded.
+    fn merge(&mut self, other: Self) {
+        let source = digest(&other.crdt);
+        self.crdt.merge(other.crdt);
+        self.trail.push(MergeRecord {
+            source,
+            result: digest(&self.crdt),
+        });
+    }
+}
+
+impl<T: CmRDT> CmRDT for Provenance<T> {
+    type Op = T::Op;
+    type Validation = T::Validation;
+
+    fn validate_op(&self, op: &Self::Op) -> Result<(), Self::Validation> {
+        self.crdt.validate_op(op)
+    }
+
+    fn apply(&mut self, op: Self::Op) {
+        self.crdt.apply(op)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::PNCounter;
+
+    #[test]
+    fn test_trail_records_each_merge() {
+        let mut b = PNCounter::new();
+        b.apply(b.inc(2u8));
+        let mut c = PNCounter::new();
+        c.apply(c.dec(3u8));
+
+        let mut a = Provenance::new(PNCounter::new());
+        a.apply(a.crdt().inc(1));
+        a.merge(Provenance::new(b.clone()));
+        let after_b = a.crdt().clone();
+        a.merge(Provenance::new(c.clone()));
+
+        assert_eq!(
+            a.trail(),
+            &[
+                MergeRecord {
+                    source: digest(&b),
+                    result: digest(&after_b),
+                },
+                MergeRecord {
+                    source: digest(&c),
+                    result: digest(a.crdt()),
+                },
+            ]
+        );
+        assert_eq!(a.crdt().read(), 1.into());
+    }
+}
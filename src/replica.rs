@@ -0,0 +1,249 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::gcounter::CounterStore;
+use crate::{orswot, pncounter, CmRDT, CvRDT, Dot, GCounter, Orswot, PNCounter};
+
+/// `Replica` binds a CRDT to the actor mutating it.
+///
+/// The mutators take no actor, they generate the op with the bound actor,
+/// apply it locally and return it so it can be shipped to other replicas.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{replica::Replica, CmRDT, PNCounter};
+///
+/// let mut a = Replica::new("A", PNCounter::new());
+/// a.inc();
+/// a.inc();
+/// let op = a.dec();
+///
+/// let mut b = PNCounter::new();
+/// b.apply(op);
+/// assert_eq!(a.crdt().read(), 1.into());
+/// assert_eq!(b.read(), (-1).into());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replica<A, T> {
+    actor: A,
+    crdt: T,
+}
+
+impl<A, T> Replica<A, T> {
+    /// Bind `crdt` to `actor`.
+    pub fn new(actor: A, crdt: T) -> Self {
+        Self { actor, crdt }
+    }
+
+    /// Returns the bound actor.
+    pub fn actor(&self) -> &A {
+        &self.actor
+    }
+
+    /// Returns a reference to the CRDT.
+    pub fn crdt(&self) -> &T {
+        &self.crdt
+    }
+
+    /// Unwraps the CRDT.
+    pub fn into_inner(self) -> T {
+        self.crdt
+    }
+}
+
+impl<A, T: CmRDT> Replica<A, T>
+where
+    T::Op: Clone,
+{
+    fn apply_local(&mut self, op: T::Op) -> T::Op {
+        self.crdt.apply(op.clone());
+        op
+    }
+
+    /// Apply an op received from another replica.
+    pub fn apply(&mut self, op: T::Op) {
+        self.crdt.apply(op)
+    }
+}
+
+impl<A, T: CvRDT> Replica<A, T> {
+    /// Merge the state of another replica.
+    pub fn merge(&mut self, other: T) {
+        self.crdt.merge(other)
+    }
+}
+
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> Replica<A, GCounter<A, S>> {
+    /// Increment the counter.
+    pub fn inc(&mut self) -> Dot<A> {
+        let op = self.crdt.inc(self.actor.clone());
+        self.apply_local(op)
+    }
+
+    /// Increment the counter by a number of steps.
+    pub fn inc_many(&mut self, steps: u64) -> Dot<A> {
+        let op = self.crdt.inc_many(self.actor.clone(), steps);
+        self.apply_local(op)
+    }
+}
+
+impl<A: Ord + Clone + Debug, S: CounterStore<A>> Replica<A, PNCounter<A, S>> {
+    /// Increment the counter.
+    pub fn inc(&mut self) -> pncounter::Op<A> {
+        let op = self.crdt.inc(self.actor.clone());
+        self.apply_local(op)
+    }
+
+    /// Decrement the counter.
+    pub fn dec(&mut self) -> pncounter::Op<A> {
+        let op = self.crdt.dec(self.actor.clone());
+        self.apply_local(op)
+    }
+
+    /// Increment the counter by a number of steps.
+    pub fn inc_many(&mut self, steps: u64) -> pncounter::Op<A> {
+        let op = self.crdt.inc_many(self.actor.clone(), steps);
+        self.apply_local(op)
+    }
+
+    /// Decrement the counter by a number of steps.
+    pub fn dec_many(&mut self, steps: u64) -> pncounter::Op<A> {
+        let op = self.crdt.dec_many(self.actor.clone(), steps);
+        self.apply_local(op)
+    }
+}
+
+impl<M: Hash + Eq + Clone, A: Ord + Hash + Clone + Debug> Replica<A, Orswot<M, A>> {
+    /// Add a member to the set.
+    pub fn add(&mut self, member: M) -> orswot::Op<M, A> {
+        let ctx = self.crdt.read_ctx().derive_add_ctx(self.actor.clone());
+        let op = self.crdt.add(member, ctx);
+        self.apply_local(op)
+    }
+
+    /// Add multiple members to the set.
+    pub fn add_all<I: IntoIterator<Item = M>>(&mut self, members: I) -> orswot::Op<M, A> {
+        let ctx = self.crdt.read_ctx().derive_add_ctx(self.actor.clone());
+        let op = self.crdt.add_all(members, ctx);
+        self.apply_local(op)
+    }
+
+    /// Remove a member from the set, as it was last observed by this replica.
+    pub fn rm(&mut self, member: M) -> orswot::Op<M, A> {
+        let ctx = self.crdt.contains(&member).derive_rm_ctx();
+        let op = self.crdt.rm(member, ctx);
+        self.apply_local(op)
+    }
+}
+
+/// `Replicated` pairs a CRDT with a broadcast sink, every op generated
+/// through `mutate` is handed to the sink and applied locally.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{replica::Replicated, GCounter};
+/// use std::sync::mpsc::channel;
+///
+/// let (tx, rx) = channel();
+/// let mut a = Replicated::new(GCounter::new(), move |op: &_| tx.send(*op).unwrap());
+/// a.mutate(|counter| counter.inc(1));
+///
+/// assert_eq!(a.crdt().read(), 1u8.into());
+/// assert_eq!(rx.recv().unwrap(), crdts::Dot::new(1, 1));
+/// ```
+#[derive(Debug)]
+pub struct Replicated<T, F> {
+    crdt: T,
+    sink: F,
+}
+
+impl<T: CmRDT, F: Fn(&T::Op)> Replicated<T, F> {
+    /// Pair `crdt` with the broadcast `sink`.
+    pub fn new(crdt: T, sink: F) -> Self {
+        Self { crdt, sink }
+    }
+
+    /// Generate an op from the CRDT with `f`, hand it to the sink, then
+    /// apply it locally.
+    pub fn mutate(&mut self, f: impl FnOnce(&T) -> T::Op) {
+        let op = f(&self.crdt);
+        (self.sink)(&op);
+        self.crdt.apply(op);
+    }
+
+    /// Apply an op received from another replica, without broadcasting it.
+    pub fn apply(&mut self, op: T::Op) {
+        self.crdt.apply(op)
+    }
+
+    /// Returns a reference to the CRDT.
+    pub fn crdt(&self) -> &T {
+        &self.crdt
+    }
+
+    /// Unwraps the CRDT.
+    pub fn into_inner(self) -> T {
+        self.crdt
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_replicated_applies_and_broadcasts() {
+        let (tx, rx) = channel();
+        let mut a = Replicated::new(Orswot::new(), move |op: &orswot::Op<_, _>| {
+            tx.send(op.clone()).unwrap()
+        });
+        a.mutate(|set| set.add(1, set.read_ctx().derive_add_ctx("A")));
+        a.mutate(|set| set.add(2, set.read_ctx().derive_add_ctx("A")));
+        a.mutate(|set| set.rm(1, set.contains(&1).derive_rm_ctx()));
+        assert_eq!(a.crdt().read().val, vec![2].into_iter().collect());
+
+        let mut b = Orswot::new();
+        for op in rx.try_iter() {
+            b.apply(op);
+        }
+        assert_eq!(&b, a.crdt());
+
+        // ops from other replicas are applied but not broadcast
+        a.apply(b.add(3, b.read_ctx().derive_add_ctx("B")));
+        assert!(a.crdt().contains(&3).val);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_counter_mutators_use_bound_actor() {
+        let mut a = Replica::new("A", GCounter::new());
+        a.inc();
+        let op = a.inc_many(3);
+        assert_eq!(op, Dot::new("A", 4));
+        assert_eq!(a.crdt().read(), 4u8.into());
+
+        let mut b = Replica::new("B", GCounter::new());
+        b.inc();
+        b.merge(a.into_inner());
+        assert_eq!(b.crdt().read(), 5u8.into());
+    }
+
+    #[test]
+    fn test_set_mutators_use_bound_actor() {
+        let mut a = Replica::new("A", Orswot::new());
+        let mut b = Replica::new("B", Orswot::new());
+
+        b.apply(a.add_all(vec![1, 2]));
+        b.apply(a.rm(1));
+        b.add(3);
+        a.apply(b.add(4));
+
+        assert_eq!(a.crdt().read().val, vec![2, 4].into_iter().collect());
+        assert_eq!(b.crdt().read().val, vec![2, 3, 4].into_iter().collect());
+        assert_eq!(b.crdt().clock().get(&"A"), 1);
+    }
+}
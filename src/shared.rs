@@ -0,0 +1,144 @@
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{CmRDT, CvRDT};
+
+/// `SharedCrdt` shares a CRDT between threads behind an `Arc<RwLock<_>>`.
+///
+/// Clones are handles to the same CRDT. `read` takes the read lock, so
+/// readers don't block each other, while `merge` and `apply` take the write
+/// lock and wait for readers to finish. Each call holds the lock for its
+/// whole duration: a `read` sees a consistent snapshot, never a half
+/// applied merge.
+///
+/// If a thread panics while merging or applying, the lock is poisoned and
+/// every later call panics too, since the CRDT may be left half updated.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{shared::SharedCrdt, CmRDT, GCounter};
+///
+/// let counter = SharedCrdt::new(GCounter::new());
+/// let handle = counter.clone();
+/// handle.apply(handle.read(|c| c.inc("A")));
+/// assert_eq!(counter.read(|c| c.read()), 1u8.into());
+/// ```
+#[derive(Debug, Default)]
+pub struct SharedCrdt<T> {
+    inner: Arc<RwLock<T>>,
+}
+
+impl<T> Clone for SharedCrdt<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> SharedCrdt<T> {
+    /// Share `crdt` behind a new lock.
+    pub fn new(crdt: T) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(crdt)),
+        }
+    }
+
+    /// Run `f` on the CRDT under the read lock and return its result.
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.read_lock())
+    }
+
+    /// Returns a clone of the current state.
+    ///
+    /// The clone carries its own clocks, derive write contexts from the
+    /// `ReadCtx` returned by its reads.
+    pub fn snapshot(&self) -> T
+    where
+        T: Clone,
+    {
+        self.read(T::clone)
+    }
+
+    fn read_lock(&self) -> RwLockReadGuard<'_, T> {
+        self.inner.read().expect("SharedCrdt lock poisoned")
+    }
+
+    fn write_lock(&self) -> RwLockWriteGuard<'_, T> {
+        self.inner.write().expect("SharedCrdt lock poisoned")
+    }
+}
+
+impl<T: CvRDT> SharedCrdt<T> {
+    /// Merge `other` into the CRDT under the write lock.
+    pub fn merge(&self, other: T) {
+        self.write_lock().merge(other)
+    }
+}
+
+impl<T: CmRDT> SharedCrdt<T> {
+    /// Apply `op` to the CRDT under the write lock.
+    pub fn apply(&self, op: T::Op) {
+        self.write_lock().apply(op)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::thread;
+
+    use crate::{CmRDT, GCounter, Orswot};
+
+    #[test]
+    fn test_concurrent_merges_and_reads_converge() {
+        let shared = SharedCrdt::new(Orswot::new());
+
+        let handles: Vec<_> = (0..8u8)
+            .map(|actor| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    let mut local = Orswot::new();
+                    for member in 0..10u8 {
+                        local.apply(
+                            local.add((actor, member), local.read_ctx().derive_add_ctx(actor)),
+                        );
+                        shared.merge(local.clone());
+                        // every read sees whole merges, all of this actor's adds so far
+                        let seen =
+                            shared.read(|set| (0..=member).all(|m| set.contains(&(actor, m)).val));
+                        assert!(seen);
+                    }
+                    local
+                })
+            })
+            .collect();
+
+        let mut expected = Orswot::new();
+        for handle in handles {
+            expected.merge(handle.join().unwrap());
+        }
+        assert_eq!(shared.snapshot(), expected);
+        assert_eq!(shared.read(|set| set.members().val.count()), 80);
+    }
+
+    #[test]
+    fn test_apply_through_clones() {
+        let counter = SharedCrdt::new(GCounter::new());
+        let handles: Vec<_> = (0..4u8)
+            .map(|actor| {
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        counter.apply(counter.read(|c| c.inc(actor)));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.read(|c| c.read()), 100u8.into());
+    }
+}
@@ -0,0 +1,90 @@
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "json")]
+use std::collections::HashMap;
+
+/// A key-value backend CRDT state can be persisted to.
+///
+/// The CRDT's themselves know nothing about storage, implement this trait
+/// over sled, files, etc. to keep application code storage-agnostic.
+pub trait Store {
+    /// The error returned by the backend when it fails to load or save.
+    type Error: std::error::Error;
+
+    /// Load the value saved under `key`, `None` if nothing was saved there.
+    fn load<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Self::Error>;
+
+    /// Save `value` under `key`, replacing whatever was saved there before.
+    fn save<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>;
+}
+
+/// An in-memory `Store` keeping values JSON encoded, enabled by the `json`
+/// feature.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use crdts::{CmRDT, GCounter, store::{MemoryStore, Store}};
+///
+/// let mut store = MemoryStore::new();
+/// let mut a = GCounter::new();
+/// a.apply(a.inc("A".to_string()));
+///
+/// store.save("hits", &a).unwrap();
+/// assert_eq!(store.load("hits").unwrap(), Some(a));
+/// # }
+/// ```
+#[cfg(feature = "json")]
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStore {
+    values: HashMap<String, Vec<u8>>,
+}
+
+#[cfg(feature = "json")]
+impl MemoryStore {
+    /// Produce a new empty `MemoryStore`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+#[cfg(feature = "json")]
+impl Store for MemoryStore {
+    type Error = serde_json::Error;
+
+    fn load<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Self::Error> {
+        self.values
+            .get(key)
+            .map(|bytes| serde_json::from_slice(bytes))
+            .transpose()
+    }
+
+    fn save<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), Self::Error> {
+        let bytes = serde_json::to_vec(value)?;
+        self.values.insert(key.to_string(), bytes);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod test {
+    use super::*;
+
+    use crate::{CmRDT, PNCounter};
+
+    #[test]
+    fn test_save_and_load_pncounter() {
+        let mut store = MemoryStore::new();
+        let mut a = PNCounter::new();
+        a.apply(a.inc_many(1u64, 5));
+        a.apply(a.dec(2u64));
+
+        assert_eq!(store.load::<PNCounter<u64>>("counter").unwrap(), None);
+        store.save("counter", &a).unwrap();
+        assert_eq!(store.load("counter").unwrap(), Some(a.clone()));
+
+        a.apply(a.inc(2u64));
+        store.save("counter", &a).unwrap();
+        assert_eq!(store.load("counter").unwrap(), Some(a));
+    }
+}
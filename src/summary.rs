@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of a replica's value and causal progress, e.g. for a status
+/// endpoint to spot replicas that are stuck or lagging behind their peers.
+///
+/// Returned by `GCounter::summary`, `PNCounter::summary` and
+/// `Orswot::summary`.
+///
+/// ```
+/// use crdts::{CmRDT, PNCounter};
+///
+/// let mut a = PNCounter::new();
+/// a.apply(a.inc("A"));
+/// a.apply(a.dec("B"));
+///
+/// let summary = a.summary();
+/// assert_eq!(summary.value, 0.into());
+/// assert_eq!(summary.actor_count, 2);
+/// assert_eq!(summary.causal_length, 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CrdtSummary<V> {
+    /// The value of a counter, or the number of members of a set
+    pub value: V,
+    /// The number of actors that have contributed to the replica
+    pub actor_count: usize,
+    /// The number of ops the replica has seen, saturating at `u64::MAX`
+    pub causal_length: u64,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{CmRDT, GCounter, Orswot, PNCounter};
+
+    #[test]
+    fn test_summary_matches_accessors() {
+        let mut g = GCounter::new();
+        g.apply(g.inc_many(1u8, 3));
+        g.apply(g.inc(2));
+        let summary = g.summary();
+        assert_eq!(summary.value, g.read());
+        assert_eq!(summary.actor_count, g.to_sparse().len());
+        assert_eq!(summary.causal_length, 4);
+
+        let mut p = PNCounter::new();
+        p.apply(p.inc_many(1u8, 3));
+        p.apply(p.dec(2));
+        p.apply(p.dec(1));
+        let summary = p.summary();
+        assert_eq!(summary.value, p.read());
+        assert_eq!(summary.actor_count, 2);
+        assert_eq!(summary.causal_length, 5);
+
+        let mut s = Orswot::new();
+        s.apply(s.add(1u8, s.read_ctx().derive_add_ctx('A')));
+        s.apply(s.add(2, s.read_ctx().derive_add_ctx('B')));
+        s.apply(s.rm(1, s.contains(&1).derive_rm_ctx()));
+        let summary = s.summary();
+        assert_eq!(summary.value, s.read().val.len());
+        assert_eq!(summary.actor_count, s.clock().iter().count());
+        assert_eq!(summary.causal_length, s.clock().causal_length());
+    }
+}
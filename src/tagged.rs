@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use crate::{CmRDT, CvRDT};
+
+/// `Tagged` gives a CRDT instance its own type, so that ops generated for
+/// one instance can't be applied to another.
+///
+/// Two `PNCounter<String>`'s accept each other's ops, applying an op to the
+/// wrong counter silently corrupts it. Wrapped with distinct `Tag` types,
+/// their ops become `TaggedOp`'s of distinct types and the compiler rejects
+/// the mixup.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{pncounter::TaggedPNCounter, CmRDT};
+///
+/// struct Likes;
+///
+/// let mut likes: TaggedPNCounter<&str, Likes> = Default::default();
+/// let op = likes.op(|counter| counter.inc("A"));
+/// likes.apply(op);
+/// assert_eq!(likes.crdt().read(), 1.into());
+/// ```
+///
+/// Ops of differently tagged counters don't unify:
+///
+/// ```compile_fail
+/// use crdts::{pncounter::TaggedPNCounter, CmRDT};
+///
+/// struct Likes;
+/// struct Views;
+///
+/// let likes: TaggedPNCounter<&str, Likes> = Default::default();
+/// let mut views: TaggedPNCounter<&str, Views> = Default::default();
+/// views.apply(likes.op(|counter| counter.inc("A")));
+/// ```
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Tagged<T, Tag> {
+    crdt: T,
+    #[serde(skip)]
+    tag: PhantomData<fn() -> Tag>,
+}
+
+/// An op generated for a `Tagged` CRDT, see `Tagged::op`.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TaggedOp<O, Tag> {
+    op: O,
+    #[serde(skip)]
+    tag: PhantomData<fn() -> Tag>,
+}
+
+impl<T, Tag> Tagged<T, Tag> {
+    /// Tag a CRDT.
+    pub fn new(crdt: T) -> Self {
+        Tagged {
+            crdt,
+            tag: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the CRDT.
+    pub fn crdt(&self) -> &T {
+        &self.crdt
+    }
+
+    /// Unwraps the CRDT.
+    pub fn into_inner(self) -> T {
+        self.crdt
+    }
+}
+
+impl<T: CmRDT, Tag> Tagged<T, Tag> {
+    /// Generate an op from the CRDT with `f`, tagged for this CRDT.
+    pub fn op(&self, f: impl FnOnce(&T) -> T::Op) -> TaggedOp<T::Op, Tag> {
+        TaggedOp::new(f(&self.crdt))
+    }
+}
+
+impl<O, Tag> TaggedOp<O, Tag> {
+    /// Tag an op. Prefer `Tagged::op`, this is for rebuilding received ops.
+    pub fn new(op: O) -> Self {
+        TaggedOp {
+            op,
+            tag: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the untagged op.
+    pub fn get(&self) -> &O {
+        &self.op
+    }
+
+    /// Unwraps the op.
+    pub fn into_inner(self) -> O {
+        self.op
+    }
+}
+
+impl<T: CmRDT, Tag> CmRDT for Tagged<T, Tag> {
+    type Op = TaggedOp<T::Op, Tag>;
+    type Validation = T::Validation;
+
+    fn validate_op(&self, op: &Self::Op) -> Result<(), Self::Validation> {
+        self.crdt.validate_op(&op.op)
+    }
+
+    fn apply(&mut self, op: Self::Op) {
+        self.crdt.apply(op.op)
+    }
+}
+
+impl<T: CvRDT, Tag> CvRDT for Tagged<T, Tag> {
+    type Validation = T::Validation;
+
+    fn validate_merge(&self, other: &Self) -> Result<(), Self::Validation> {
+        self.crdt.validate_merge(&other.crdt)
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.crdt.merge(other.crdt)
+    }
+}
+
+impl<T: Default, Tag> Default for Tagged<T, Tag> {
+    fn default() -> Self {
+        Tagged::new(T::default())
+    }
+}
+
+impl<T: Clone, Tag> Clone for Tagged<T, Tag> {
+    fn clone(&self) -> Self {
+        Tagged::new(self.crdt.clone())
+    }
+}
+
+impl<T: PartialEq, Tag> PartialEq for Tagged<T, Tag> {
+    fn eq(&self, other: &Self) -> bool {
+        self.crdt == other.crdt
+    }
+}
+
+impl<T: Eq, Tag> Eq for Tagged<T, Tag> {}
+
+impl<T: Hash, Tag> Hash for Tagged<T, Tag> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.crdt.hash(state)
+    }
+}
+
+impl<T: Debug, Tag> Debug for Tagged<T, Tag> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Tagged").field(&self.crdt).finish()
+    }
+}
+
+impl<O: Clone, Tag> Clone for TaggedOp<O, Tag> {
+    fn clone(&self) -> Self {
+        TaggedOp::new(self.op.clone())
+    }
+}
+
+impl<O: PartialEq, Tag> PartialEq for TaggedOp<O, Tag> {
+    fn eq(&self, other: &Self) -> bool {
+        self.op == other.op
+    }
+}
+
+impl<O: Eq, Tag> Eq for TaggedOp<O, Tag> {}
+
+impl<O: Hash, Tag> Hash for TaggedOp<O, Tag> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.op.hash(state)
+    }
+}
+
+impl<O: Debug, Tag> Debug for TaggedOp<O, Tag> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TaggedOp").field(&self.op).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::pncounter::TaggedPNCounter;
+
+    struct Likes;
+
+    #[test]
+    fn test_tagged_counter_behaves_like_its_counter() {
+        let mut a: TaggedPNCounter<&str, Likes> = Default::default();
+        let mut b = a.clone();
+        a.apply(a.op(|c| c.inc("A")));
+        let op = b.op(|c| c.dec("B"));
+        b.apply(op.clone());
+        b.apply(op);
+        a.merge(b);
+        assert_eq!(a.crdt().read(), 0.into());
+        assert_eq!(a.into_inner().read(), 0.into());
+    }
+
+    #[test]
+    fn test_tagged_op_serializes_as_inner_op() {
+        let a: TaggedPNCounter<u8, Likes> = Default::default();
+        let op = a.op(|c| c.inc(1));
+        let json = serde_json::to_string(&op).unwrap();
+        assert_eq!(json, serde_json::to_string(op.get()).unwrap());
+        assert_eq!(
+            serde_json::from_str::<TaggedOp<_, Likes>>(&json).unwrap(),
+            op
+        );
+    }
+}
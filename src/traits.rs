@@ -1,3 +1,4 @@
+use std::any::Any;
 use std::error::Error;
 use std::hash::Hash;
 
@@ -36,6 +37,45 @@ pub trait CvRDT {
     fn merge(&mut self, other: Self);
 }
 
+/// Object safe counterpart of `CvRDT`, for holding CRDT's behind `Box<dyn DynCvRDT>`.
+///
+/// Every `CvRDT + Clone + 'static` implements this trait.
+pub trait DynCvRDT: Any {
+    /// Returns self as `Any` so it can be passed to another `merge_dyn`.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Merge `other` into self if it is of the same concrete type.
+    ///
+    /// Returns `false`, leaving self untouched, if the types don't match.
+    fn merge_dyn(&mut self, other: &dyn Any) -> bool;
+}
+
+impl<T: CvRDT + Clone + 'static> DynCvRDT for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn merge_dyn(&mut self, other: &dyn Any) -> bool {
+        match other.downcast_ref::<T>() {
+            Some(other) => {
+                self.merge(other.clone());
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// State based CRDT's with a neutral element for merge.
+pub trait CvRDTIdentity: CvRDT {
+    /// Returns the merge identity, merging it into any state is a no-op:
+    ///
+    /// `x.merge(Self::identity())` leaves `x` unchanged for every `x`.
+    ///
+    /// Generic folds over many states can safely start from this element.
+    fn identity() -> Self;
+}
+
 /// Operation based CRDT's replicate by transmitting each operation.
 pub trait CmRDT {
     /// Op defines a mutation to the CRDT.
@@ -82,3 +122,49 @@ pub trait ResetRemove<A: Ord> {
     /// Remove data that is strictly smaller than this clock
     fn reset_remove(&mut self, clock: &VClock<A>);
 }
+
+/// Causally safe garbage collection of CRDT metadata.
+pub trait Compact<A: Ord> {
+    /// Reclaim history that is below `stable`, a clock that every replica
+    /// is known to have seen (e.g. the `glb` of all replica clocks).
+    ///
+    /// Compacting against a true causal stability clock never changes the
+    /// observable state.
+    fn compact(&mut self, stable: &VClock<A>);
+}
+
+/// CRDT's with a canonical encoding: states that compare equal encode to
+/// the same bytes, whatever the iteration order of their hash maps, so the
+/// bytes can be digested to compare replicas across processes.
+///
+/// The implementations in this crate need the `json` feature.
+pub trait CanonicalBytes {
+    /// Returns the canonical encoding of the state.
+    fn canonical_bytes(&self) -> Vec<u8>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{CmRDT, GCounter, PNCounter};
+
+    #[test]
+    fn test_merge_boxed_gcounters() {
+        let mut a = GCounter::new();
+        a.apply(a.inc("A"));
+        let mut b = GCounter::new();
+        b.apply(b.inc_many("B", 2));
+
+        let mut boxed_a: Box<dyn DynCvRDT> = Box::new(a.clone());
+        let boxed_b: Box<dyn DynCvRDT> = Box::new(b.clone());
+        assert!(boxed_a.merge_dyn(boxed_b.as_any()));
+
+        a.merge(b);
+        assert_eq!(boxed_a.as_any().downcast_ref::<GCounter<&str>>(), Some(&a));
+
+        let other_type: Box<dyn DynCvRDT> = Box::new(PNCounter::<&str>::new());
+        assert!(!boxed_a.merge_dyn(other_type.as_any()));
+        assert_eq!(boxed_a.as_any().downcast_ref::<GCounter<&str>>(), Some(&a));
+    }
+}
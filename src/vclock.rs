@@ -13,15 +13,18 @@
 //! ```
 
 use std::cmp::{self, Ordering};
-use std::collections::{btree_map, BTreeMap};
+use std::collections::{btree_map, BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Display};
 use std::hash::Hash;
-use std::mem;
 
 use serde::{Deserialize, Serialize};
 
 use crate::quickcheck::{Arbitrary, Gen};
-use crate::{traits::VacuousValidation, CmRDT, CvRDT, Dot, DotRange, ResetRemove};
+#[cfg(feature = "json")]
+use crate::CanonicalBytes;
+use crate::{
+    traits::VacuousValidation, CmRDT, Compact, CrdtError, CvRDT, Dot, DotRange, ResetRemove,
+};
 
 /// A `VClock` is a standard vector clock.
 /// It contains a set of "actors" and associated counters.
@@ -35,9 +38,105 @@ use crate::{traits::VacuousValidation, CmRDT, CvRDT, Dot, DotRange, ResetRemove}
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct VClock<A: Ord> {
     /// dots is the mapping from actors to their associated counters
+    ///
+    /// The dots are serialized as a sequence of `{ "actor": .., "count": .. }`
+    /// entries rather than a map so that actors which can't be used as map
+    /// keys in formats like JSON (e.g. integers or tuples) still round-trip.
+    /// Clocks serialized by earlier versions as a map are still accepted.
+    ///
+    /// This is a breaking change to the wire format of every CRDT holding a
+    /// clock: peers on earlier releases can't decode the sequence from the
+    /// self-describing formats such as JSON or CBOR, so upgrade the readers
+    /// before the writers. Bincode writes both encodings as the same bytes.
+    #[serde(
+        with = "dots_serde",
+        bound(serialize = "A: Serialize", deserialize = "A: Deserialize<'de>")
+    )]
     pub dots: BTreeMap<A, u64>,
 }
 
+/// Serializes a map of actor counts as a sequence of `{ "actor": .., "count": .. }`
+/// entries, see `VClock::dots`.
+pub(crate) mod dots_serde {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::marker::PhantomData;
+
+    use serde::de::{MapAccess, SeqAccess, Visitor};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize)]
+    struct EntryRef<'a, A> {
+        actor: &'a A,
+        count: u64,
+    }
+
+    #[derive(Deserialize)]
+    struct Entry<A> {
+        actor: A,
+        count: u64,
+    }
+
+    pub fn serialize<A: Serialize, S: Serializer>(
+        dots: &BTreeMap<A, u64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(dots.iter().map(|(actor, &count)| EntryRef { actor, count }))
+    }
+
+    pub fn deserialize<'de, A: Ord + Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<A, u64>, D::Error> {
+        let visitor = DotsVisitor(PhantomData);
+        if deserializer.is_human_readable() {
+            // self describing, so clocks written as a map still decode
+            deserializer.deserialize_any(visitor)
+        } else {
+            // bincode writes a map and a sequence of entries as the same
+            // bytes; CBOR is self describing and hands the maps of earlier
+            // versions to `visit_map` whatever we ask for
+            deserializer.deserialize_seq(visitor)
+        }
+    }
+
+    struct DotsVisitor<A>(PhantomData<A>);
+
+    impl<'de, A: Ord + Deserialize<'de>> Visitor<'de> for DotsVisitor<A> {
+        type Value = BTreeMap<A, u64>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a sequence of actor counts")
+        }
+
+        fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Self::Value, S::Error> {
+            let mut dots = BTreeMap::new();
+            while let Some(Entry { actor, count }) = seq.next_element()? {
+                observe(&mut dots, actor, count);
+            }
+            Ok(dots)
+        }
+
+        fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+            let mut dots = BTreeMap::new();
+            while let Some((actor, count)) = map.next_entry()? {
+                observe(&mut dots, actor, count);
+            }
+            Ok(dots)
+        }
+    }
+
+    fn observe<A: Ord>(dots: &mut BTreeMap<A, u64>, actor: A, count: u64) {
+        if count == 0 {
+            // an actor with a zero count is the same as an absent one
+            return;
+        }
+        // a well formed clock never repeats an actor, but if it does
+        // we keep the largest count, the same result as applying each dot
+        let counter = dots.entry(actor).or_insert(0);
+        *counter = std::cmp::max(*counter, count);
+    }
+}
+
 impl<A: Ord> Default for VClock<A> {
     fn default() -> Self {
         Self {
@@ -84,19 +183,35 @@ impl<A: Ord> ResetRemove<A> for VClock<A> {
     /// count in the given vclock
     fn reset_remove(&mut self, other: &Self) {
         for Dot { actor, counter } in other.iter() {
-            if counter >= self.get(&actor) {
-                self.dots.remove(&actor);
+            if counter >= self.get(actor) {
+                self.dots.remove(actor);
             }
         }
     }
 }
 
+impl<A: Ord> Compact<A> for VClock<A> {
+    /// A `VClock` holds no history, it's all needed to order future events.
+    fn compact(&mut self, _stable: &VClock<A>) {}
+}
+
+/// The pairs encoded by `VClock::canonical_bytes`.
+#[cfg(feature = "json")]
+pub(crate) type CanonicalPairs<'a, A> = Vec<(&'a A, u64)>;
+
+#[cfg(feature = "json")]
+impl<A: Ord + Serialize> CanonicalBytes for VClock<A> {
+    fn canonical_bytes(&self) -> Vec<u8> {
+        VClock::canonical_bytes(self)
+    }
+}
+
 impl<A: Ord + Clone + Debug> CmRDT for VClock<A> {
     type Op = Dot<A>;
     type Validation = DotRange<A>;
 
     fn validate_op(&self, dot: &Self::Op) -> Result<(), Self::Validation> {
-        let next_counter = self.get(&dot.actor) + 1;
+        let next_counter = self.get(&dot.actor).saturating_add(1);
         if dot.counter > next_counter {
             Err(DotRange {
                 actor: dot.actor.clone(),
@@ -156,7 +271,7 @@ impl<A: Ord> VClock<A> {
         A: Clone,
     {
         let mut cloned = self.clone();
-        cloned.reset_remove(&base_clock);
+        cloned.reset_remove(base_clock);
         cloned
     }
 
@@ -201,6 +316,20 @@ impl<A: Ord> VClock<A> {
         Dot::new(actor, counter)
     }
 
+    /// Returns `true` if this clock has already seen the given dot, that is,
+    /// the clock's counter for `dot.actor` is at least `dot.counter`.
+    ///
+    /// # Examples
+    /// ```
+    /// use crdts::{VClock, Dot};
+    /// let clock = VClock::from(Dot::new("A", 3));
+    /// assert!(clock.dominates_dot(&Dot::new("A", 3)));
+    /// assert!(!clock.dominates_dot(&Dot::new("A", 4)));
+    /// ```
+    pub fn dominates_dot(&self, dot: &Dot<A>) -> bool {
+        self.get(&dot.actor) >= dot.counter
+    }
+
     /// True if two vector clocks have diverged.
     ///
     /// # Examples
@@ -215,6 +344,135 @@ impl<A: Ord> VClock<A> {
         self.partial_cmp(other).is_none()
     }
 
+    /// Returns `true` if self happened strictly before `other`,
+    /// that is, `other` has seen everything self has seen and more.
+    ///
+    /// ```
+    /// use crdts::{Dot, VClock};
+    /// let a = VClock::from(Dot::new("A", 1));
+    /// let b = VClock::from(Dot::new("A", 2));
+    /// assert!(a.happened_before(&b));
+    /// assert!(!b.happened_before(&a));
+    /// assert!(!a.happened_before(&a));
+    /// ```
+    pub fn happened_before(&self, other: &VClock<A>) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Less)
+    }
+
+    /// Returns `true` if self happened strictly after `other`.
+    pub fn happened_after(&self, other: &VClock<A>) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Greater)
+    }
+
+    /// Returns the sum of all counters in this clock, saturating at `u64::MAX`.
+    ///
+    /// This is a cheap scalar measure of how advanced a clock is, useful
+    /// for heuristics like picking the most up to date replica. It is no
+    /// substitute for the causal partial order.
+    pub fn causal_length(&self) -> u64 {
+        self.dots
+            .values()
+            .fold(0, |length, counter| length.saturating_add(*counter))
+    }
+
+    /// Apply a batch of dots, taking the max counter per actor.
+    ///
+    /// Equivalent to calling `apply` for each dot, but with a single map
+    /// lookup per dot.
+    pub fn apply_dots(&mut self, dots: impl IntoIterator<Item = Dot<A>>) {
+        for Dot { actor, counter } in dots {
+            if counter == 0 {
+                continue;
+            }
+            let entry = self.dots.entry(actor).or_insert(counter);
+            *entry = cmp::max(*entry, counter);
+        }
+    }
+
+    /// Fast-forward self to dominate `other`, e.g. after receiving all of
+    /// a peer's state out of band. This is the join of the two clocks,
+    /// returning true iff any of our counters advanced.
+    pub fn advance_to(&mut self, other: &VClock<A>) -> bool
+    where
+        A: Clone,
+    {
+        let mut advanced = false;
+        for (actor, counter) in other.dots.iter() {
+            if *counter > self.get(actor) {
+                self.dots.insert(actor.clone(), *counter);
+                advanced = true;
+            }
+        }
+        advanced
+    }
+
+    /// Returns the actors whose counter in self exceeds their counter in `other`.
+    ///
+    /// These are the actors whose ops `other` is missing, so they're all we
+    /// need to push to bring it up to date.
+    pub fn actors_ahead_of(&self, other: &VClock<A>) -> Vec<A>
+    where
+        A: Clone,
+    {
+        self.dots
+            .iter()
+            .filter(|(actor, counter)| **counter > other.get(actor))
+            .map(|(actor, _)| actor.clone())
+            .collect()
+    }
+
+    /// Returns the clock as `(actor, counter)` pairs sorted by actor, a
+    /// compact alternative to the serialized form for the wire.
+    pub fn pack(&self) -> Vec<(A, u64)>
+    where
+        A: Clone,
+    {
+        self.dots
+            .iter()
+            .map(|(actor, counter)| (actor.clone(), *counter))
+            .collect()
+    }
+
+    /// Returns a canonical encoding of the clock: the JSON array of its
+    /// non-zero `(actor, counter)` pairs in actor order. Clocks that compare
+    /// equal have the same canonical bytes, so they can be hashed to check
+    /// replicas for integrity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an actor fails to serialize.
+    #[cfg(feature = "json")]
+    pub fn canonical_bytes(&self) -> Vec<u8>
+    where
+        A: Serialize,
+    {
+        serde_json::to_vec(&self.canonical_pairs()).expect("failed to serialize actor")
+    }
+
+    /// The non-zero `(actor, counter)` pairs in actor order.
+    #[cfg(feature = "json")]
+    pub(crate) fn canonical_pairs(&self) -> CanonicalPairs<'_, A> {
+        self.dots
+            .iter()
+            .filter(|(_, counter)| **counter > 0)
+            .map(|(actor, counter)| (actor, *counter))
+            .collect()
+    }
+
+    /// Rebuild a clock from `(actor, counter)` pairs, the inverse of `pack`.
+    ///
+    /// The pairs may come in any order. An actor listed more than once keeps
+    /// its largest counter and counters of 0 are dropped, the same as
+    /// applying each pair as a dot.
+    pub fn unpack(entries: Vec<(A, u64)>) -> VClock<A> {
+        let mut dots = BTreeMap::new();
+        for (actor, counter) in entries.into_iter().filter(|(_, c)| *c > 0) {
+            let entry = dots.entry(actor).or_insert(0);
+            *entry = cmp::max(*entry, counter);
+        }
+        VClock { dots }
+    }
+
     /// Returns `true` if this vector clock contains nothing.
     pub fn is_empty(&self) -> bool {
         self.dots.is_empty()
@@ -236,9 +494,33 @@ impl<A: Ord> VClock<A> {
         Self { dots }
     }
 
+    /// Returns a new `VClock` containing only the entries of the given actors.
+    ///
+    /// # Examples
+    /// ```
+    /// use crdts::{VClock, Dot};
+    /// use std::collections::BTreeSet;
+    ///
+    /// let clock: VClock<_> = vec![Dot::new("A", 1), Dot::new("B", 2)].into_iter().collect();
+    /// let actors: BTreeSet<_> = vec!["A"].into_iter().collect();
+    /// assert_eq!(clock.restrict_to(&actors), VClock::from(Dot::new("A", 1)));
+    /// ```
+    pub fn restrict_to(&self, actors: &BTreeSet<A>) -> VClock<A>
+    where
+        A: Clone,
+    {
+        let dots = self
+            .dots
+            .iter()
+            .filter(|(actor, _)| actors.contains(actor))
+            .map(|(actor, counter)| (actor.clone(), *counter))
+            .collect();
+        Self { dots }
+    }
+
     /// Reduces this VClock to the greatest-lower-bound of the given
     /// VClock and itsef, as an example see the following code.
-    /// ``` rust
+    /// ```
     /// use crdts::{VClock, Dot, ResetRemove, CmRDT};
     /// let mut c = VClock::new();
     /// c.apply(Dot::new(23, 6));
@@ -254,7 +536,7 @@ impl<A: Ord> VClock<A> {
     /// assert_eq!(c.get(&43), 0);
     /// ```
     pub fn glb(&mut self, other: &Self) {
-        self.dots = mem::replace(&mut self.dots, BTreeMap::new())
+        self.dots = std::mem::take(&mut self.dots)
             .into_iter()
             .filter_map(|(actor, count)| {
                 // Since an actor missing from the dots map has an implied
@@ -352,3 +634,135 @@ impl<A: Ord + Clone + Debug + Arbitrary> Arbitrary for VClock<A> {
         Box::new(shrunk_clocks.into_iter())
     }
 }
+
+/// A `FixedVClock` is a vector clock for a cluster of exactly `N` replicas,
+/// where each replica is identified by its position `0..N`.
+///
+/// The counters are kept inline in an array, so unlike `VClock` it never
+/// allocates. Merge is the element-wise max and the partial order is the
+/// same as `VClock`'s. Indexing past `N` panics, like slice indexing,
+/// `validate_op` rejects ops for replicas past `N` with
+/// `CrdtError::UnknownActor`.
+///
+/// # Examples
+///
+/// ```
+/// use crdts::{vclock::FixedVClock, CmRDT};
+///
+/// let mut a = FixedVClock::<3>::new();
+/// a.apply(a.inc(0));
+/// let b = a;
+/// a.apply(a.inc(2));
+/// assert!(a > b);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FixedVClock<const N: usize> {
+    counters: [u64; N],
+}
+
+impl<const N: usize> Default for FixedVClock<N> {
+    fn default() -> Self {
+        Self { counters: [0; N] }
+    }
+}
+
+impl<const N: usize> PartialOrd for FixedVClock<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let pairs = || self.counters.iter().zip(other.counters.iter());
+        if self == other {
+            Some(Ordering::Equal)
+        } else if pairs().all(|(ours, theirs)| ours >= theirs) {
+            Some(Ordering::Greater)
+        } else if pairs().all(|(ours, theirs)| ours <= theirs) {
+            Some(Ordering::Less)
+        } else {
+            None
+        }
+    }
+}
+
+impl<const N: usize> CmRDT for FixedVClock<N> {
+    type Op = Dot<usize>;
+    type Validation = CrdtError<usize>;
+
+    fn validate_op(&self, dot: &Self::Op) -> Result<(), Self::Validation> {
+        if dot.actor >= N {
+            return Err(CrdtError::UnknownActor { actor: dot.actor });
+        }
+        let next_counter = self.get(dot.actor).saturating_add(1);
+        if dot.counter > next_counter {
+            Err(CrdtError::DotGap(DotRange {
+                actor: dot.actor,
+                counter_range: next_counter..dot.counter,
+            }))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn apply(&mut self, dot: Self::Op) {
+        let counter = &mut self.counters[dot.actor];
+        *counter = cmp::max(*counter, dot.counter);
+    }
+}
+
+impl<const N: usize> CvRDT for FixedVClock<N> {
+    type Validation = VacuousValidation;
+
+    fn validate_merge(&self, _other: &Self) -> Result<(), Self::Validation> {
+        Ok(())
+    }
+
+    fn merge(&mut self, other: Self) {
+        for (ours, theirs) in self.counters.iter_mut().zip(other.counters.iter()) {
+            *ours = cmp::max(*ours, *theirs);
+        }
+    }
+}
+
+impl<const N: usize> FixedVClock<N> {
+    /// Returns a new `FixedVClock` with every counter at 0.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Return the counter of the replica at this position.
+    pub fn get(&self, replica: usize) -> u64 {
+        self.counters[replica]
+    }
+
+    /// Generate the Op to increment the counter of the replica at this position.
+    /// The counter saturates at `u64::MAX` rather than overflowing, see
+    /// `checked_inc`.
+    pub fn inc(&self, replica: usize) -> Dot<usize> {
+        Dot::new(replica, self.get(replica).saturating_add(1))
+    }
+
+    /// Generate the Op to increment the counter of the replica at this position,
+    /// or `CrdtError::Overflow` if the counter is already at `u64::MAX`.
+    pub fn checked_inc(&self, replica: usize) -> Result<Dot<usize>, CrdtError<usize>> {
+        match self.get(replica).checked_add(1) {
+            Some(counter) => Ok(Dot::new(replica, counter)),
+            None => Err(CrdtError::Overflow { actor: replica }),
+        }
+    }
+
+    /// Returns the counters, indexed by replica position.
+    pub fn counters(&self) -> &[u64; N] {
+        &self.counters
+    }
+
+    /// Convert to a `VClock` keyed by replica position, leaving out
+    /// replicas whose counter is 0.
+    pub fn to_vclock(&self) -> VClock<usize> {
+        VClock {
+            dots: self
+                .counters
+                .iter()
+                .enumerate()
+                .filter(|(_, counter)| **counter > 0)
+                .map(|(replica, counter)| (replica, *counter))
+                .collect(),
+        }
+    }
+}
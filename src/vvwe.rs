@@ -293,7 +293,7 @@ mod test {
             exceptions: HashSet::new(),
         };
 
-        let c: HashSet<LogTime> = (0..10).into_iter().collect();
+        let c: HashSet<LogTime> = (0..10).collect();
         assert_eq!(b.diff_from(&a), c);
     }
 
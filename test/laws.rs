@@ -0,0 +1,3 @@
+use crdts::{crdt_laws, Dot, GCounter};
+
+crdt_laws!(GCounter<u8>, Dot<u8>);
@@ -181,11 +181,9 @@ fn test_reapply_lseq_ops() {
         site2.apply(insert_op.clone());
         site2.apply(insert_op.clone());
 
-        let delete_op = site2.delete_index(ix).expect(&format!(
-            "ixxxx@{} was out of bounds@{}",
-            ix,
-            site2.len()
-        ));
+        let delete_op = site2
+            .delete_index(ix)
+            .unwrap_or_else(|| panic!("ixxxx@{} was out of bounds@{}", ix, site2.len()));
         // apply op a coupel of times
         site2.apply(delete_op.clone());
         site2.apply(delete_op.clone());
@@ -230,10 +228,9 @@ fn test_insert_followed_by_deletes() {
         site1.apply(insert_op.clone());
         site2.apply(insert_op);
 
-        let delete_op =
-            site2
-                .delete_index(ix)
-                .expect(&format!("ix@{} was out of bounds@{}", ix, site2.len()));
+        let delete_op = site2
+            .delete_index(ix)
+            .unwrap_or_else(|| panic!("ix@{} was out of bounds@{}", ix, site2.len()));
         site2.apply(delete_op.clone());
         site1.apply(delete_op);
     }
@@ -1,4 +1,7 @@
-use crdts::{map, mvreg, CmRDT, CvRDT, Dot, DotRange, MVReg, Map, ResetRemove, VClock};
+use crdts::{
+    map, mvreg, CmRDT, Compact, CvRDT, Dot, DotRange, MVReg, Map, Orswot, PNCounter, ResetRemove,
+    VClock,
+};
 use quickcheck::TestResult;
 
 type TActor = u8;
@@ -6,8 +9,9 @@ type TKey = u8;
 type TVal = MVReg<u8, TActor>;
 type TOp = map::Op<TKey, Map<TKey, TVal, TActor>, TActor>;
 type TMap = Map<TKey, Map<TKey, TVal, TActor>, TActor>;
+type TOpsPrim = (u8, Vec<(u8, u8, u8, u8, u8)>);
 
-fn build_ops(prims: (u8, Vec<(u8, u8, u8, u8, u8)>)) -> (TActor, Vec<TOp>) {
+fn build_ops(prims: TOpsPrim) -> (TActor, Vec<TOp>) {
     let (actor, ops_data) = prims;
 
     let mut ops = Vec::new();
@@ -121,6 +125,29 @@ fn test_update() {
     );
 }
 
+#[test]
+fn test_read_modify_write_keeps_concurrent_write() {
+    let mut a: Map<&str, MVReg<u8, &str>, &str> = Map::new();
+    a.apply(a.update("x", a.read_ctx().derive_add_ctx("A"), |reg, ctx| {
+        reg.write(1, ctx)
+    }));
+    let mut b = a.clone();
+
+    let (reg, ctx) = a.get(&"x").split();
+    let next = reg.unwrap().read().val[0] + 1;
+    a.apply(a.update("x", ctx.derive_add_ctx("A"), |reg, ctx| {
+        reg.write(next, ctx)
+    }));
+    b.apply(b.update("x", b.get(&"x").derive_add_ctx("B"), |reg, ctx| {
+        reg.write(5, ctx)
+    }));
+
+    a.merge(b);
+    let mut vals = a.get(&"x").val.unwrap().read().val;
+    vals.sort_unstable();
+    assert_eq!(vals, vec![2, 5]);
+}
+
 #[test]
 fn test_remove() {
     let mut m: TMap = Map::new();
@@ -577,8 +604,8 @@ fn apply_ops(map: &mut TMap, ops: &[TOp]) {
 quickcheck! {
     // TODO: add test to show equivalence of merge and Op exchange
     fn prop_op_exchange_same_as_merge(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpsPrim,
+        ops2_prim: TOpsPrim
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -603,8 +630,8 @@ quickcheck! {
     }
 
     fn prop_op_exchange_converges(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpsPrim,
+        ops2_prim: TOpsPrim
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -631,9 +658,9 @@ quickcheck! {
     }
 
     fn prop_op_exchange_associative(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops3_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpsPrim,
+        ops2_prim: TOpsPrim,
+        ops3_prim: TOpsPrim
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -664,7 +691,7 @@ quickcheck! {
     }
 
     fn prop_op_idempotent(
-        ops_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops_prim: TOpsPrim
     ) -> bool {
         let ops = build_ops(ops_prim);
         let mut m = TMap::new();
@@ -677,9 +704,9 @@ quickcheck! {
     }
 
     fn prop_op_associative(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops3_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpsPrim,
+        ops2_prim: TOpsPrim,
+        ops3_prim: TOpsPrim
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -711,9 +738,9 @@ quickcheck! {
 
 
     fn prop_merge_associative(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops3_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpsPrim,
+        ops2_prim: TOpsPrim,
+        ops3_prim: TOpsPrim
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -745,8 +772,8 @@ quickcheck! {
     }
 
     fn prop_merge_commutative(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpsPrim,
+        ops2_prim: TOpsPrim
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -774,8 +801,8 @@ quickcheck! {
 
 
     fn prop_merge_followed_by_merge(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops1_prim: TOpsPrim,
+        ops2_prim: TOpsPrim
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
         let ops2 = build_ops(ops2_prim);
@@ -801,7 +828,7 @@ quickcheck! {
     }
 
     fn prop_merge_idempotent(
-        ops_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops_prim: TOpsPrim
     ) -> bool {
         let ops = build_ops(ops_prim);
 
@@ -817,7 +844,7 @@ quickcheck! {
     }
 
     fn prop_reset_remove_with_empty_vclock_is_nop(
-        ops_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops_prim: TOpsPrim
     ) -> bool {
         let ops = build_ops(ops_prim);
 
@@ -831,7 +858,7 @@ quickcheck! {
     }
 
     fn prop_reset_remove_with_map_clock_is_empty_map(
-        ops_prim: (u8, Vec<(u8, u8, u8, u8, u8)>)
+        ops_prim: TOpsPrim
     ) -> bool {
         let mut m = TMap::new();
         apply_ops(&mut m, &build_ops(ops_prim).1);
@@ -845,8 +872,8 @@ quickcheck! {
     }
 
     fn prop_reset_remove_than_merge_same_as_merge_than_reset_remove(
-        ops1_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
-        ops2_prim: (u8, Vec<(u8, u8, u8, u8, u8)>),
+        ops1_prim: TOpsPrim,
+        ops2_prim: TOpsPrim,
         vclock: VClock<u8>
     ) -> TestResult {
         let ops1 = build_ops(ops1_prim);
@@ -876,3 +903,49 @@ quickcheck! {
         TestResult::from_bool(m1_reset_remove_after == m1)
     }
 }
+
+#[test]
+fn test_dump_shows_keys_and_values() {
+    let mut m: Map<&str, Map<&str, PNCounter<&str>, &str>, &str> = Map::new();
+
+    let add_ctx = m.read_ctx().derive_add_ctx("A");
+    m.apply(m.update("likes", add_ctx, |inner, ctx| {
+        inner.update("alice", ctx, |c, ctx| c.inc_many(ctx.dot.actor, 3))
+    }));
+
+    let add_ctx = m.read_ctx().derive_add_ctx("A");
+    m.apply(m.update("dislikes", add_ctx, |inner, ctx| {
+        inner.update("bob", ctx, |c, ctx| c.dec(ctx.dot.actor))
+    }));
+
+    assert_eq!(
+        m.dump(),
+        "Map\n  dislikes => Map\n    bob => -1\n  likes => Map\n    alice => 3"
+    );
+}
+
+#[test]
+fn test_compact_preserves_observable_state() {
+    let mut a: Map<u8, Orswot<u8, u8>, u8> = Map::new();
+    let mut b = a.clone();
+
+    let add_ctx = b.read_ctx().derive_add_ctx(2);
+    b.apply(b.update(1, add_ctx, |set, ctx| set.add(7, ctx)));
+    let rm_op = b.rm(1, b.get(&1).derive_rm_ctx());
+
+    // `a` sees the remove before the add it removes, so it's deferred
+    a.apply(rm_op);
+    let before = a.clone();
+    a.compact(&VClock::new());
+    assert_eq!(a, before);
+
+    a.merge(b.clone());
+    let stable = a.read_ctx().add_clock;
+    let read_before = a.keys().map(|key| *key.val).collect::<Vec<_>>();
+    a.compact(&stable);
+    assert_eq!(
+        a.keys().map(|key| *key.val).collect::<Vec<_>>(),
+        read_before
+    );
+    assert!(a.get(&1).val.is_none());
+}
@@ -70,6 +70,23 @@ fn test_concurrent_update_with_same_value_dont_collapse_on_apply() {
     );
 }
 
+#[test]
+fn test_resolve_picks_from_concurrent_values() {
+    let mut reg: MVReg<u8, &str> = MVReg::new();
+    assert_eq!(reg.resolve(|vals| vals[0]), None);
+
+    let op_a = reg.write(3, reg.read_ctx().derive_add_ctx("A"));
+    let op_b = reg.write(7, reg.read_ctx().derive_add_ctx("B"));
+    reg.apply(op_a);
+    reg.apply(op_b);
+
+    assert_eq!(reg.read().val.len(), 2);
+    assert_eq!(
+        reg.resolve(|vals| vals.iter().max().copied().unwrap()),
+        Some(&7)
+    );
+}
+
 #[test]
 fn test_multi_val() {
     let mut r1 = MVReg::new();
@@ -115,7 +132,7 @@ fn ops_are_not_compatible(opss: &[&Vec<(u8, u8)>]) -> bool {
                 a_clock.apply(a_clock.inc(*a_actor));
                 b_clock.apply(b_clock.inc(*b_actor));
 
-                if b_clock.get(&a_actor) == a_clock.get(&a_actor) {
+                if b_clock.get(a_actor) == a_clock.get(a_actor) {
                     // this check is a bit broad as it's not a failure
                     // to insert the same value with the same actor version
                     // but for simplicity we reject those ops as well
@@ -302,3 +319,47 @@ quickcheck! {
         TestResult::from_bool(true)
     }
 }
+
+#[test]
+fn test_with_max_values_bounds_concurrent_writes() {
+    let actors: Vec<u8> = (0..6).collect();
+    let ops: Vec<_> = actors
+        .iter()
+        .map(|actor| {
+            let reg: MVReg<u8, u8> = MVReg::new();
+            reg.write(*actor * 10, reg.read_ctx().derive_add_ctx(*actor))
+        })
+        .collect();
+
+    let mut a = MVReg::with_max_values(3);
+    let mut b = MVReg::with_max_values(3);
+    for op in ops.iter() {
+        a.apply(op.clone());
+    }
+    for op in ops.iter().rev() {
+        b.apply(op.clone());
+    }
+    assert_eq!(a.read().val.len(), 3);
+    assert_eq!(a, b);
+
+    // all the writes have the same causal length, the highest actors win
+    let mut vals = a.read().val;
+    vals.sort();
+    assert_eq!(vals, vec![30, 40, 50]);
+
+    let mut unbounded = MVReg::new();
+    for op in ops {
+        unbounded.apply(op);
+    }
+    let mut merged = MVReg::with_max_values(3);
+    merged.merge(unbounded.clone());
+    assert_eq!(merged, a);
+    assert_eq!(unbounded.read().val.len(), 6);
+
+    // a write observing the bounded register supersedes what it kept
+    let op = a.write(99, a.read_ctx().derive_add_ctx(0));
+    a.apply(op.clone());
+    b.apply(op);
+    assert_eq!(a.read().val, vec![99]);
+    assert_eq!(a, b);
+}
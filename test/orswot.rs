@@ -1,7 +1,7 @@
 extern crate crdts;
 extern crate rand;
 
-use crdts::{ctx::ReadCtx, orswot::Op, *};
+use crdts::{ctx::ReadCtx, gcounter::MergeProgress, orswot::Op, *};
 use std::collections::HashSet;
 use std::iter::once;
 
@@ -32,10 +32,14 @@ quickcheck! {
                     orswot.apply(op);
                     for member in members {
                         let removed = ops.iter().any(|op| {
-                            if let Op::Rm { clock, members } = op {
-                                members.contains(&member) && clock.dot(dot.actor) >= dot
-                            } else {
-                                false
+                            match op {
+                                Op::Rm { clock, members } => {
+                                    members.contains(&member) && clock.dot(dot.actor) >= dot
+                                }
+                                Op::Rename { clock, old, .. } => {
+                                    old == &member && clock.dot(dot.actor) >= dot
+                                }
+                                Op::Add { .. } => false,
                             }
                         });
 
@@ -51,7 +55,10 @@ quickcheck! {
                     );
                 }
             }
-            Op::Rm { .. } => assert_eq!(orswot.validate_op(&op), Ok(()))
+            Op::Rm { .. } => assert_eq!(orswot.validate_op(&op), Ok(())),
+            Op::Rename { dot, .. } => {
+                assert_eq!(orswot.validate_op(&op), orswot.clock().validate_op(&dot))
+            }
         }
 
         true
@@ -87,14 +94,16 @@ quickcheck! {
             let merged_members = merged.read().val;
 
             for ReadCtx { val, rm_clock, .. } in orswot_1.iter() {
-                if !merged_members.contains(&val) {
+                if !merged_members.contains(val) {
                     let mut val_clock = rm_clock.clone();
                     for op in ops_2.iter() {
                         match op {
-                            Op::Rm { clock, .. } => val_clock.reset_remove(clock),
+                            Op::Rm { clock, .. } | Op::Rename { clock, .. } => {
+                                val_clock.reset_remove(clock)
+                            }
                             Op::Add { members, dot } => {
                                 if members.is_empty() {
-                                    val_clock.reset_remove(&dot.clone().into());
+                                    val_clock.reset_remove(&(*dot).into());
                                 }
                             }
                         }
@@ -104,14 +113,16 @@ quickcheck! {
             }
 
             for ReadCtx { val, rm_clock, .. } in orswot_2.iter() {
-                if !merged_members.contains(&val) {
+                if !merged_members.contains(val) {
                     let mut val_clock = rm_clock.clone();
                     for op in ops_1.iter() {
                         match op {
-                            Op::Rm { clock, .. } => val_clock.reset_remove(clock),
+                            Op::Rm { clock, .. } | Op::Rename { clock, .. } => {
+                                val_clock.reset_remove(clock)
+                            }
                             Op::Add { members, dot } => {
                                 if members.is_empty() {
-                                    val_clock.reset_remove(&dot.clone().into());
+                                    val_clock.reset_remove(&(*dot).into());
                                 }
                             }
                         }
@@ -124,45 +135,169 @@ quickcheck! {
         true
     }
 
-    fn prop_merge_converges(ops: Vec<Op<Member, Actor>>) -> bool {
-        // Different interleavings of ops applied to different
-        // orswots should all converge when merged. Apply the
-        // ops to increasing numbers of witnessing orswots,
-        // then merge them together and make sure they have
-        // all converged.
-        let mut result = None;
-        for i in 2..ACTOR_MAX {
-            let mut witnesses: Vec<Orswot<Member, Actor>> =
-                (0..i).map(|_| Orswot::new()).collect();
-            for op in ops.iter() {
-                let actor_opt = match op {
-                    Op::Add { dot, ..} => Some(dot.actor),
-                    Op::Rm { clock, ..} => clock.iter().next().map(|d| *d.actor) // any actor will do for Rm
-                };
-
-                if let Some(actor) = actor_opt {
-                    let witness = &mut witnesses[(actor % i) as usize];
-                    witness.apply(op.clone());
-                }
+    fn prop_merge_budgeted_equals_merge(
+        ops_1: Vec<Op<Member, Actor>>,
+        ops_2: Vec<Op<Member, Actor>>,
+        budget: u8
+    ) -> bool {
+        let mut orswot_1 = Orswot::new();
+        let mut orswot_2 = Orswot::new();
+        for op in ops_1 {
+            if orswot_1.validate_op(&op).is_ok() {
+                orswot_1.apply(op)
+            }
+        }
+        for op in ops_2 {
+            if orswot_2.validate_op(&op).is_ok() {
+                orswot_2.apply(op)
             }
+        }
+        if orswot_1.validate_merge(&orswot_2).is_err() {
+            return true;
+        }
+
+        let mut full = orswot_1.clone();
+        full.merge(orswot_2.clone());
+
+        let budget = usize::from(budget % 4) + 1;
+        let mut calls = 0;
+        while orswot_1.merge_budgeted(&orswot_2, budget) == MergeProgress::Pending {
+            calls += 1;
+            assert!(calls <= usize::from(ACTOR_MAX));
+        }
+        orswot_1 == full
+    }
+
+    fn prop_merge_limit_equals_merge_then_gc(
+        ops_1: Vec<Op<Member, Actor>>,
+        ops_2: Vec<Op<Member, Actor>>
+    ) -> bool {
+        let mut orswot_1 = Orswot::new();
+        let mut orswot_2 = Orswot::new();
+        for op in ops_1 {
+            orswot_1.apply(op)
+        }
+        for op in ops_2 {
+            orswot_2.apply(op)
+        }
+        let mut stable = orswot_1.clock();
+        stable.glb(&orswot_2.clock());
+
+        let mut gced = orswot_1.clone();
+        gced.merge(orswot_2.clone());
+        gced.gc(&stable);
+
+        orswot_1.merge_limit(orswot_2, &stable);
+        orswot_1 == gced
+    }
+
+    fn prop_delta_since_equals_merge(
+        ops_1: Vec<Op<Member, Actor>>,
+        ops_2: Vec<Op<Member, Actor>>,
+        ops_3: Vec<Op<Member, Actor>>
+    ) -> bool {
+        let mut orswot_1 = Orswot::new();
+        let mut orswot_2 = Orswot::new();
+        for op in ops_1 {
+            orswot_1.apply(op)
+        }
+        for op in ops_2 {
+            orswot_2.apply(op)
+        }
+        let lagging = orswot_1.clone();
 
-            let mut merged = Orswot::new();
-            for witness in witnesses {
-                merged.merge(witness);
+        if orswot_1.validate_merge(&orswot_2).is_ok() {
+            orswot_1.merge(orswot_2);
+        }
+        for op in ops_3 {
+            orswot_1.apply(op)
+        }
+
+        let mut full = lagging.clone();
+        full.merge(orswot_1.clone());
+        let mut caught_up = lagging.clone();
+        caught_up.merge(orswot_1.delta_since(&lagging.clock()));
+        caught_up == full
+    }
+
+    fn prop_events_track_membership(
+        ops_1: Vec<Op<Member, Actor>>,
+        ops_2: Vec<Op<Member, Actor>>
+    ) -> bool {
+        use crdts::orswot::SetEvent;
+
+        let mut orswot_1 = Orswot::new();
+        let events = orswot_1.subscribe();
+        let mut orswot_2 = Orswot::new();
+        for op in ops_1 {
+            orswot_1.apply(op)
+        }
+        for op in ops_2 {
+            orswot_2.apply(op)
+        }
+        orswot_1.merge(orswot_2);
+
+        let mut members = HashSet::new();
+        for event in events.try_iter() {
+            match event {
+                SetEvent::Added(member) => assert!(members.insert(member)),
+                SetEvent::Removed(member) => assert!(members.remove(&member)),
             }
+        }
+        members == orswot_1.read().val
+    }
 
-            if let Some(ref prev_res) = result {
-                if prev_res != &merged {
-                    println!("opvec: {:?}", ops);
-                    println!("result: {:?}", result);
-                    println!("merged: {:?}", merged);
-                    return false;
-                };
+    fn prop_compact_preserves_observable_state(ops: Vec<Op<Member, Actor>>) -> bool {
+        let mut a = Orswot::new();
+        let mut b = Orswot::new();
+        for op in ops {
+            let to_a = match &op {
+                Op::Add { dot, .. } | Op::Rename { dot, .. } => dot.actor % 2 == 0,
+                Op::Rm { clock, .. } => clock.iter().next().map(|d| d.actor % 2 == 0).unwrap_or(true),
+            };
+            if to_a {
+                a.apply(op);
             } else {
-                result = Some(merged);
+                b.apply(op);
             }
         }
-        true
+
+        let mut stable = a.clock();
+        stable.glb(&b.clock());
+
+        let mut compact_a = a.clone();
+        compact_a.compact(&stable);
+        let mut compact_b = b.clone();
+        compact_b.compact(&stable);
+        let mut merged = a.clone();
+        merged.merge(b);
+        let mut compact_merged = compact_a.clone();
+        compact_merged.merge(compact_b);
+
+        let mut compact_full = merged.clone();
+        compact_full.compact(&merged.clock());
+
+        compact_a.read().val == a.read().val
+            && compact_merged.read().val == merged.read().val
+            && compact_full.read().val == merged.read().val
+    }
+
+    fn prop_identity_is_merge_neutral(ops: Vec<Op<Member, Actor>>) -> bool {
+        let mut orswot = Orswot::new();
+        for op in ops {
+            orswot.apply(op);
+        }
+
+        let mut merged = orswot.clone();
+        merged.merge(Orswot::identity());
+        merged == orswot
+    }
+}
+
+#[cfg(feature = "testing")]
+quickcheck! {
+    fn prop_merge_converges(ops: Vec<Op<Member, Actor>>) -> bool {
+        crdts::orswot::orswot_converges(ops)
     }
 }
 
@@ -181,7 +316,6 @@ fn weird_highlight_1() {
     assert!(a.read().val.is_empty());
 }
 
-///
 #[test]
 fn adds_dont_destroy_causality() {
     let mut a = Orswot::new();
@@ -227,7 +361,7 @@ fn merge_clocks_of_identical_entries() {
     let mut final_clock = VClock::new();
     final_clock.apply(final_clock.inc("A"));
     final_clock.apply(final_clock.inc("B"));
-    assert_eq!(a.contains(&1).val, true);
+    assert!(a.contains(&1).val);
     assert_eq!(a.contains(&1).rm_clock, final_clock);
 }
 
@@ -366,3 +500,789 @@ fn test_reset_remove_semantics() {
         vec![2].into_iter().collect()
     );
 }
+
+#[test]
+fn test_merge_after_does_not_resurrect_below_barrier() {
+    let mut a = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    let stale = a.clone();
+
+    a.apply(a.rm(1, a.contains(&1).derive_rm_ctx()));
+    a.apply(a.add(2, a.read().derive_add_ctx("B")));
+
+    // every replica has seen A's first dot, so the history below it is collected
+    let barrier = VClock::from(Dot::new("A", 1));
+    a.reset_remove(&barrier);
+
+    let mut resurrected = a.clone();
+    resurrected.merge(stale.clone());
+    assert!(resurrected.contains(&1).val);
+
+    a.merge_after(&stale, &barrier);
+    assert!(!a.contains(&1).val);
+    assert!(a.contains(&2).val);
+}
+
+#[test]
+fn test_delta_since_carries_removes() {
+    let mut a = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    a.apply(a.add(2, a.read().derive_add_ctx("B")));
+    a.apply(a.add(3, a.read().derive_add_ctx("C")));
+
+    let lagging = a.clone();
+
+    a.apply(a.rm(1, a.contains(&1).derive_rm_ctx()));
+    a.apply(a.add(4, a.read().derive_add_ctx("B")));
+
+    let delta = a.delta_since(&lagging.clock());
+    assert_eq!(
+        delta.read().val,
+        vec![2, 4].into_iter().collect::<HashSet<_>>()
+    );
+
+    let mut full = lagging.clone();
+    full.merge(a.clone());
+
+    let mut caught_up = lagging.clone();
+    caught_up.merge(delta);
+
+    assert_eq!(caught_up, full);
+    assert_eq!(caught_up.read().val, vec![2, 3, 4].into_iter().collect());
+    assert_eq!(a.delta_since(&a.clock()).read().val, HashSet::new());
+}
+
+#[test]
+fn test_clear_keeps_concurrent_adds() {
+    let mut a = Orswot::new();
+    a.apply(a.add_all(vec![1, 2], a.read().derive_add_ctx("A")));
+    let mut b = a.clone();
+
+    b.apply(b.add(3, b.read().derive_add_ctx("B")));
+    let clear_op = a.clear();
+    a.apply(clear_op.clone());
+    assert!(a.read().val.is_empty());
+
+    let mut merged = a.clone();
+    merged.merge(b.clone());
+    assert_eq!(merged.read().val, once(3).collect());
+
+    b.apply(clear_op);
+    assert_eq!(b.read().val, once(3).collect());
+}
+
+#[test]
+fn test_contains_at_detects_advanced_context() {
+    let mut a = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    let expected = a.contains(&1).rm_clock;
+    assert!(a.contains_at(&1, &expected));
+    assert!(!a.contains_at(&2, &expected));
+
+    let mut b = a.clone();
+    b.apply(b.add(1, b.read().derive_add_ctx("B")));
+    a.merge(b);
+    assert!(!a.contains_at(&1, &expected));
+    assert!(a.contains_at(&1, &a.contains(&1).rm_clock));
+
+    a.apply(a.rm(1, a.contains(&1).derive_rm_ctx()));
+    assert!(!a.contains_at(&1, &expected));
+}
+
+#[test]
+fn test_with_capacity_rejects_growth_past_bound() {
+    let mut a = Orswot::with_capacity(2);
+    a.apply(a.add_all(vec![1, 2], a.read().derive_add_ctx("A")));
+    assert_eq!(a.read().val, vec![1, 2].into_iter().collect());
+
+    let op = a.add(3, a.read().derive_add_ctx("A"));
+    assert_eq!(
+        a.check_capacity(&op),
+        Err(orswot::CapacityExceeded { capacity: 2 })
+    );
+    let before = a.clone();
+    assert_eq!(
+        a.apply_checked(op),
+        Err(CrdtError::CapacityExceeded { capacity: 2 })
+    );
+    assert_eq!(a, before);
+    assert_eq!(a.clock(), before.clock());
+
+    // re-adding a present member stays within bound
+    let op = a.add(2, a.read().derive_add_ctx("A"));
+    assert_eq!(a.check_capacity(&op), Ok(()));
+    a.apply(op);
+
+    // removing frees up room
+    a.apply(a.rm(1, a.contains(&1).derive_rm_ctx()));
+    let op = a.add(3, a.read().derive_add_ctx("A"));
+    assert_eq!(a.check_capacity(&op), Ok(()));
+    a.apply(op);
+    assert_eq!(a.read().val, vec![2, 3].into_iter().collect());
+}
+
+#[test]
+fn test_capacity_never_drops_members_on_merge() {
+    let ops = vec![
+        Op::Add {
+            dot: Dot::new(1, 1),
+            members: vec![10],
+        },
+        Op::Add {
+            dot: Dot::new(1, 2),
+            members: vec![20],
+        },
+        Op::Rm {
+            clock: Dot::new(1, 1).into(),
+            members: vec![10],
+        },
+        Op::Add {
+            dot: Dot::new(1, 3),
+            members: vec![30],
+        },
+    ];
+
+    let mut peer = Orswot::new();
+    let mut applied = Orswot::with_capacity(1);
+    let mut checked = Orswot::with_capacity(1);
+    for op in ops {
+        peer.apply(op.clone());
+        applied.apply(op.clone());
+        let _ = checked.apply_checked(op);
+    }
+    assert_eq!(peer.read().val, vec![20, 30].into_iter().collect());
+    assert_eq!(applied, peer);
+    assert!(checked.read().val.is_empty());
+
+    let mut merged = peer.clone();
+    merged.merge(applied);
+    assert_eq!(merged, peer);
+
+    // the rejected adds never entered the bounded set's clock
+    merged.merge(checked);
+    assert_eq!(merged, peer);
+}
+
+#[test]
+fn test_rename_past_capacity_is_rejected_whole() {
+    let mut a = Orswot::with_capacity(1);
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+
+    // renaming an absent member only adds
+    let op = a.rename(&3, 2, "A");
+    let before = a.clone();
+    assert_eq!(
+        a.apply_checked(op.clone()),
+        Err(CrdtError::CapacityExceeded { capacity: 1 })
+    );
+    assert_eq!(a, before);
+
+    // apply never drops part of the op
+    a.apply(op);
+    assert!(a.contains(&1).val);
+    assert!(a.contains(&2).val);
+}
+
+#[test]
+fn test_rename_in_full_set_fits() {
+    let mut a = Orswot::with_capacity(2);
+    a.apply(a.add_all(vec![1, 2], a.read().derive_add_ctx("A")));
+    let mut b = a.clone();
+    b.apply(b.add(1, b.read().derive_add_ctx("B")));
+
+    let op = a.rename(&1, 3, "A");
+    let mut c = a.clone();
+    assert_eq!(a.apply_checked(op.clone()), Ok(()));
+    assert_eq!(a.read().val, vec![2, 3].into_iter().collect());
+    assert_eq!(a.apply_checked(a.rename(&2, 3, "A")), Ok(()));
+    assert_eq!(a.read().val, once(3).collect());
+
+    // the concurrent add of 1 survives the remove, the rename would grow c
+    c.merge(b);
+    assert_eq!(
+        c.apply_checked(op),
+        Err(CrdtError::CapacityExceeded { capacity: 2 })
+    );
+}
+
+#[test]
+fn test_map_members_preserves_contexts() {
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct User {
+        id: u32,
+        name: &'static str,
+    }
+
+    let alice = User {
+        id: 1,
+        name: "alice",
+    };
+    let bob = User { id: 2, name: "bob" };
+
+    let mut a = Orswot::new();
+    a.apply(a.add(alice.clone(), a.read().derive_add_ctx("A")));
+    let mut b = a.clone();
+    b.apply(b.add(bob.clone(), b.read().derive_add_ctx("B")));
+    b.apply(b.rm(alice.clone(), b.contains(&alice).derive_rm_ctx()));
+    a.apply(a.add(bob.clone(), a.read().derive_add_ctx("A")));
+
+    let ids_a = a.map_members(|user| user.id);
+    let ids_b = b.map_members(|user| user.id);
+    assert_eq!(ids_a.read().val, vec![1, 2].into_iter().collect());
+    assert_eq!(ids_a.clock(), a.clock());
+    assert_eq!(ids_a.contains(&2).rm_clock, a.contains(&bob).rm_clock);
+
+    let mut merged_ids = ids_a.clone();
+    merged_ids.merge(ids_b);
+    let mut merged = a.clone();
+    merged.merge(b);
+    assert_eq!(merged_ids, merged.map_members(|user| user.id));
+    assert_eq!(merged_ids.read().val, once(2).collect());
+}
+
+#[test]
+fn test_causally_equal_sees_history() {
+    let mut a = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+
+    let mut b = Orswot::new();
+    b.apply(b.add(2, b.read().derive_add_ctx("B")));
+    b.apply(b.rm(2, b.contains(&2).derive_rm_ctx()));
+    b.apply(b.add(1, b.read().derive_add_ctx("B")));
+
+    assert_eq!(a.read().val, b.read().val);
+    assert!(!a.causally_equal(&b));
+    assert_ne!(a, b);
+
+    let mut a_merged = a.clone();
+    a_merged.merge(b.clone());
+    b.merge(a);
+    assert!(a_merged.causally_equal(&b));
+}
+
+#[test]
+fn test_apply_checked_rejects_dot_gap() {
+    let mut a = Orswot::new();
+    a.apply_checked(a.add(1, a.read().derive_add_ctx("A")))
+        .unwrap();
+
+    let skipping = Op::Add {
+        dot: Dot::new("A", 5),
+        members: vec![2],
+    };
+    assert_eq!(
+        a.apply_checked(skipping),
+        Err(CrdtError::DotGap(DotRange {
+            actor: "A",
+            counter_range: 2..5,
+        }))
+    );
+    assert_eq!(a.read().val, once(1).collect());
+}
+
+#[test]
+fn test_validate_state_of_deserialized_set() {
+    let mut a: Orswot<u8, u8> = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx(7)));
+    let json = serde_json::to_string(&a).unwrap();
+    let decoded: Orswot<u8, u8> = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded.validate_state(), Ok(()));
+
+    let unknown =
+        r#"{"clock":{"dots":[]},"entries":{"1":{"dots":[{"actor":7,"count":1}]}},"deferred":{}}"#;
+    let decoded: Orswot<u8, u8> = serde_json::from_str(unknown).unwrap();
+    assert_eq!(
+        decoded.validate_state(),
+        Err(CrdtError::UnknownActor { actor: 7 })
+    );
+
+    let ahead = r#"{"clock":{"dots":[{"actor":7,"count":1}]},"entries":{"1":{"dots":[{"actor":7,"count":3}]}},"deferred":{}}"#;
+    let decoded: Orswot<u8, u8> = serde_json::from_str(ahead).unwrap();
+    assert_eq!(
+        decoded.validate_state(),
+        Err(CrdtError::DotGap(DotRange {
+            actor: 7,
+            counter_range: 2..4,
+        }))
+    );
+}
+
+#[test]
+fn test_merge_observe_reports_membership_diff() {
+    let mut a = Orswot::new();
+    a.apply(a.add_all(vec![1, 2, 3], a.read().derive_add_ctx("A")));
+    let mut b = a.clone();
+
+    b.apply(b.rm(2, b.contains(&2).derive_rm_ctx()));
+    b.apply(b.add_all(vec![3, 4, 5], b.read().derive_add_ctx("B")));
+
+    let (mut appeared, mut disappeared) = a.merge_observe(&b);
+    appeared.sort();
+    disappeared.sort();
+    assert_eq!(appeared, vec![4, 5]);
+    assert_eq!(disappeared, vec![2]);
+    assert_eq!(a.read().val, vec![1, 3, 4, 5].into_iter().collect());
+
+    assert_eq!(a.merge_observe(&b), (vec![], vec![]));
+}
+
+#[test]
+fn test_snapshot_is_frozen() {
+    let mut a = Orswot::new();
+    a.apply(a.add_all(vec![3, 1, 2], a.read().derive_add_ctx("A")));
+
+    let view = a.snapshot().val;
+    a.apply(a.rm(1, a.contains(&1).derive_rm_ctx()));
+    a.apply(a.add(4, a.read().derive_add_ctx("A")));
+
+    assert_eq!(view.len(), 3);
+    assert!(view.contains(&1));
+    assert!(!view.contains(&4));
+    assert_eq!(view.iter().cloned().collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(
+        a.snapshot().val.iter().cloned().collect::<Vec<_>>(),
+        vec![2, 3, 4]
+    );
+    assert!(Orswot::<u8, u8>::new().snapshot().val.is_empty());
+}
+
+#[test]
+fn test_add_with_ctx_imports_context() {
+    let ctx: VClock<_> = vec![Dot::new("A", 2), Dot::new("B", 1)]
+        .into_iter()
+        .collect();
+
+    let mut b = Orswot::new();
+    for op in b.add_with_ctx(2, ctx.clone()) {
+        b.apply(op);
+    }
+    assert!(b.contains_at(&2, &ctx));
+    assert_eq!(b.clock(), ctx);
+
+    // a replica that saw a remove covering the imported context drops it
+    let mut c = b.clone();
+    c.apply(c.rm(2, c.contains(&2).derive_rm_ctx()));
+    b.merge(c);
+    assert!(!b.contains(&2).val);
+}
+
+#[test]
+fn test_merge_verified_detects_reused_actor() {
+    let mut a = Orswot::new();
+    a.apply(a.add(1, a.read().derive_add_ctx("A")));
+    a.apply(a.add(2, a.read().derive_add_ctx("A")));
+
+    let mut from_a = Orswot::new();
+    assert_eq!(from_a.merge_verified(&a, |actor| *actor == "A"), Ok(()));
+    assert_eq!(from_a, a);
+
+    // a second replica misconfigured to also act as "A"
+    let mut imposter = Orswot::new();
+    imposter.apply(imposter.add(3, imposter.read().derive_add_ctx("A")));
+
+    let before = from_a.clone();
+    assert_eq!(
+        from_a.merge_verified(&imposter, |actor| *actor == "A"),
+        Err(orswot::MergeError::CounterRegression {
+            actor: "A",
+            ours: 2,
+            theirs: 1,
+        })
+    );
+    assert_eq!(from_a, before);
+
+    assert_eq!(
+        from_a.merge_verified(&imposter, |actor| *actor == "B"),
+        Err(orswot::MergeError::Invalid(
+            orswot::Validation::DoubleSpentDot {
+                dot: Dot::new("A", 1),
+                our_member: 1,
+                their_member: 3,
+            }
+        ))
+    );
+}
+
+#[test]
+fn test_members_matches_read() {
+    let mut a = Orswot::new();
+    a.apply(a.add_all(0..100, a.read().derive_add_ctx("A")));
+    a.apply(a.rm_all(vec![3, 50, 99], a.read().derive_rm_ctx()));
+
+    let members: HashSet<u8> = a.members().val.cloned().collect();
+    assert_eq!(members.len(), a.members().val.count());
+    assert_eq!(members, a.read().val);
+    assert!(!members.contains(&50));
+}
+
+#[test]
+fn test_rename_is_atomic() {
+    let mut a = Orswot::new();
+    a.apply(a.add("colour", a.read_ctx().derive_add_ctx("A")));
+    let mut b = a.clone();
+
+    let op = a.rename(&"colour", "color", "A");
+    b.apply(op.clone());
+    assert_eq!(b.read().val, vec!["color"].into_iter().collect());
+
+    // redelivering the rename is a no-op
+    b.apply(op);
+    assert_eq!(b.read().val, vec!["color"].into_iter().collect());
+
+    a.merge(b);
+    assert_eq!(a.read().val, vec!["color"].into_iter().collect());
+}
+
+#[test]
+fn test_concurrent_renames_converge() {
+    let mut a = Orswot::new();
+    a.apply(a.add("x", a.read_ctx().derive_add_ctx("A")));
+    let mut b = a.clone();
+
+    a.apply(a.rename(&"x", "y", "A"));
+    b.apply(b.rename(&"x", "z", "B"));
+
+    let mut a_then_b = a.clone();
+    a_then_b.merge(b.clone());
+    b.merge(a);
+    assert_eq!(a_then_b, b);
+    assert_eq!(b.read().val, vec!["y", "z"].into_iter().collect());
+}
+
+#[test]
+fn test_read_map_resolves_duplicate_keys() {
+    let mut a = Orswot::new();
+    let mut b = Orswot::new();
+    a.apply(a.add(("k", 1), a.read_ctx().derive_add_ctx("A")));
+    a.apply(a.add(("other", 7), a.read_ctx().derive_add_ctx("A")));
+    b.apply(b.add(("k", 5), b.read_ctx().derive_add_ctx("B")));
+
+    // both "k" pairs have causal length 1, the greater value wins
+    let mut merged = a.clone();
+    merged.merge(b.clone());
+    assert_eq!(
+        merged.read_map().val,
+        vec![("k", 5), ("other", 7)].into_iter().collect()
+    );
+
+    // a pair added with a greater causal length wins
+    a.apply(a.add(("k", 0), a.read_ctx().derive_add_ctx("A")));
+    merged.merge(a.clone());
+    b.merge(a);
+    assert_eq!(merged.read_map().val, b.read_map().val);
+    assert_eq!(merged.read_map().val.get("k"), Some(&0));
+}
+
+#[test]
+fn test_remap_actors() {
+    let mut a: Orswot<&str, u8> = Orswot::new();
+    a.apply(a.add("x", a.read_ctx().derive_add_ctx(1)));
+    a.apply(a.add("y", a.read_ctx().derive_add_ctx(2)));
+    a.apply(a.add("x", a.read_ctx().derive_add_ctx(2)));
+
+    // a remove we haven't seen the adds of yet is deferred
+    let mut rm_clock = VClock::new();
+    rm_clock.apply(Dot::new(3, 1));
+    a.apply(Op::Rm {
+        clock: rm_clock,
+        members: vec!["z"],
+    });
+
+    let remapped = a.clone().remap_actors(|actor| format!("node-{}", actor));
+    assert_eq!(remapped.read().val, a.read().val);
+    assert_eq!(
+        remapped.contains(&"x").rm_clock,
+        vec![
+            Dot::new("node-1".to_string(), 1),
+            Dot::new("node-2".to_string(), 2)
+        ]
+        .into_iter()
+        .collect()
+    );
+    assert_eq!(remapped.validate_state(), Ok(()));
+
+    // the deferred remove survives and still applies once its add arrives
+    let mut remapped = remapped;
+    remapped.apply(Op::Add {
+        dot: Dot::new("node-3".to_string(), 1),
+        members: vec!["z"],
+    });
+    assert!(!remapped.contains(&"z").val);
+}
+
+#[test]
+fn test_gc_reclaims_deferred_history() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    for member in 0..5 {
+        a.apply(a.add(member, a.read_ctx().derive_add_ctx("A")));
+    }
+
+    // removes that also witnessed adds from "C" we haven't seen yet are deferred
+    for member in 0..5 {
+        let mut clock = a.clock();
+        clock.apply(Dot::new("C", 1));
+        a.apply(Op::Rm {
+            clock,
+            members: vec![member, member + 10],
+        });
+        a.apply(a.add(member + 20, a.read_ctx().derive_add_ctx("A")));
+    }
+    let before = a.clone();
+
+    // every replica has seen "A" up to 10
+    let stable: VClock<_> = vec![Dot::new("A", 10)].into_iter().collect();
+    // each deferred clock drops "A", then the 5 removes collapse into 1
+    assert_eq!(a.gc(&stable), 5 + 4);
+    assert_eq!(a.read().val, before.read().val);
+    assert_eq!(a.gc(&stable), 0);
+
+    // the deferred removes still apply once the awaited adds arrive
+    let late_add = Op::Add {
+        dot: Dot::new("C", 1),
+        members: vec![3, 13],
+    };
+    let mut before = before;
+    before.apply(late_add.clone());
+    a.apply(late_add);
+    assert_eq!(a.read().val, before.read().val);
+    assert!(!a.contains(&13).val);
+}
+
+#[test]
+fn test_compact_reclaims_deferred_history() {
+    let mut a: Orswot<u8, &str> = Orswot::new();
+    a.apply(a.add(1, a.read_ctx().derive_add_ctx("A")));
+    for member in 0..3 {
+        let mut clock = a.clock();
+        clock.apply(Dot::new("C", 1));
+        a.apply(Op::Rm {
+            clock,
+            members: vec![member],
+        });
+        a.apply(a.add(member + 20, a.read_ctx().derive_add_ctx("A")));
+    }
+    assert_eq!(a.deferred_len(), 3);
+    let before = a.clone();
+
+    a.compact(&a.clock());
+    assert_eq!(a.deferred_len(), 1);
+    assert_eq!(a.read().val, before.read().val);
+}
+
+#[test]
+fn test_contains_all_and_any() {
+    let mut a = Orswot::new();
+    a.apply(a.add_all(vec![1, 2, 3], a.read_ctx().derive_add_ctx("A")));
+
+    assert!(a.contains_all(&[1, 3]).val);
+    assert!(!a.contains_all(&[1, 4]).val);
+    assert!(a.contains_any(&[4, 2]).val);
+    assert!(!a.contains_any(&[4, 5]).val);
+
+    assert!(a.contains_all(&[]).val);
+    assert!(!a.contains_any(&[]).val);
+
+    // removing under the read's context keeps a concurrent add
+    let mut b = a.clone();
+    b.apply(b.add(1, b.read_ctx().derive_add_ctx("B")));
+    let read = a.contains_all(&[1, 2]);
+    assert!(read.val);
+    a.apply(a.rm_all(vec![1, 2], read.derive_rm_ctx()));
+    a.merge(b);
+    assert_eq!(a.read().val, vec![1, 3].into_iter().collect());
+}
+
+#[test]
+fn test_diff_stable_ignores_compacted_history() {
+    let mut a = Orswot::new();
+    a.apply(a.add("x", a.read_ctx().derive_add_ctx("A")));
+    a.apply(a.add("x", a.read_ctx().derive_add_ctx("B")));
+    a.apply(a.add("y", a.read_ctx().derive_add_ctx("A")));
+
+    // b holds the same members, but a peer compacted "x"'s context down to
+    // its latest dot
+    let mut b = Orswot::new();
+    b.apply(Op::Add {
+        dot: Dot::new("B", 1),
+        members: vec!["x"],
+    });
+    b.apply(Op::Add {
+        dot: Dot::new("A", 2),
+        members: vec!["y"],
+    });
+
+    let stable: VClock<_> = vec![Dot::new("A", 1)].into_iter().collect();
+    assert_eq!(a.diff_stable(&b, &stable), (vec![], vec![]));
+
+    // without the stable clock the compacted dot looks like a divergence
+    assert_eq!(a.diff_stable(&b, &VClock::new()), (vec!["x"], vec![]));
+
+    // real divergence above the stable clock is still reported
+    b.apply(b.add("z", b.read_ctx().derive_add_ctx("B")));
+    b.apply(b.add("y", b.read_ctx().derive_add_ctx("B")));
+    let (ours, mut theirs) = a.diff_stable(&b, &stable);
+    theirs.sort();
+    assert_eq!((ours, theirs), (vec![], vec!["y", "z"]));
+}
+
+#[test]
+fn test_event_of_translates_ops() {
+    use crdts::orswot::{event_of, SetEvent};
+
+    let mut a = Orswot::new();
+    let add = a.add(1, a.read_ctx().derive_add_ctx("A"));
+    assert_eq!(event_of(&add), vec![SetEvent::Added(1)]);
+    a.apply(add);
+
+    let batch = a.add_all(vec![2, 3], a.read_ctx().derive_add_ctx("A"));
+    assert_eq!(
+        event_of(&batch),
+        vec![SetEvent::Added(2), SetEvent::Added(3)]
+    );
+    a.apply(batch);
+
+    let rm = a.rm_all(vec![1, 2], a.read_ctx().derive_rm_ctx());
+    assert_eq!(
+        event_of(&rm),
+        vec![SetEvent::Removed(1), SetEvent::Removed(2)]
+    );
+    a.apply(rm);
+
+    let rename = a.rename(&3, 4, "A");
+    assert_eq!(
+        event_of(&rename),
+        vec![SetEvent::Removed(3), SetEvent::Added(4)]
+    );
+}
+
+#[test]
+fn test_merge_limit_bounds_churn_history() {
+    // a noisy peer adds and removes the same members, each remove also
+    // witnessing an add from "C" it hasn't seen yet
+    let mut peer: Orswot<u8, &str> = Orswot::new();
+    for round in 0..50 {
+        let member = round % 3;
+        peer.apply(peer.add(member, peer.read_ctx().derive_add_ctx("B")));
+        let mut clock = peer.clock();
+        clock.apply(Dot::new("C", 1));
+        peer.apply(Op::Rm {
+            clock,
+            members: vec![member],
+        });
+    }
+    peer.apply(peer.add(7, peer.read_ctx().derive_add_ctx("B")));
+    assert_eq!(peer.deferred_len(), 50);
+
+    let mut plain = Orswot::new();
+    plain.merge(peer.clone());
+
+    let stable = peer.clock();
+    let mut limited = Orswot::new();
+    assert_eq!(limited.merge_limit(peer, &stable), 50 + 49);
+    assert_eq!(limited.deferred_len(), 1);
+    assert_eq!(limited.read().val, plain.read().val);
+    assert_eq!(limited.read().val, vec![7].into_iter().collect());
+
+    // the collapsed removes still apply once C's add arrives
+    let late_add = Op::Add {
+        dot: Dot::new("C", 1),
+        members: vec![0, 1, 7],
+    };
+    plain.apply(late_add.clone());
+    limited.apply(late_add);
+    assert_eq!(limited.read().val, plain.read().val);
+}
+
+#[test]
+fn test_subscribe_receives_membership_changes() {
+    use crdts::orswot::SetEvent;
+
+    let mut a = Orswot::new();
+    let first = a.subscribe();
+    let second = a.subscribe();
+
+    a.apply(a.add(1u8, a.read_ctx().derive_add_ctx(1u8)));
+    a.apply(a.add(1, a.read_ctx().derive_add_ctx(1)));
+    a.apply(a.rename(&1, 2, 1));
+
+    let mut b = Orswot::new();
+    b.apply(b.add(3, b.read_ctx().derive_add_ctx(2)));
+    a.merge(b.clone());
+    // nothing changes, nothing is sent
+    a.merge(b);
+
+    let expected = vec![
+        SetEvent::Added(1),
+        SetEvent::Removed(1),
+        SetEvent::Added(2),
+        SetEvent::Added(3),
+    ];
+    assert_eq!(first.try_iter().collect::<Vec<_>>(), expected);
+    assert_eq!(second.try_iter().collect::<Vec<_>>(), expected);
+
+    // clones start without subscribers
+    let mut c = a.clone();
+    c.apply(c.add(4, c.read_ctx().derive_add_ctx(1)));
+    assert_eq!(first.try_recv().ok(), None);
+}
+
+#[test]
+fn test_reset_remove_notifies_subscribers() {
+    use crdts::orswot::SetEvent;
+
+    let mut a = Orswot::new();
+    a.apply(a.add(1u8, a.read_ctx().derive_add_ctx(1u8)));
+    a.apply(a.add(2, a.read_ctx().derive_add_ctx(2)));
+    let events = a.subscribe();
+
+    // compacting never changes the membership
+    a.compact(&a.clock());
+    assert_eq!(events.try_recv().ok(), None);
+
+    a.reset_remove(&Dot::new(1, 1).into());
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        vec![SetEvent::Removed(1)]
+    );
+    assert_eq!(a.read().val, vec![2].into_iter().collect());
+}
+
+#[test]
+fn test_merge_budgeted_in_steps() {
+    let mut big = Orswot::new();
+    for actor in 0..10u8 {
+        for member in 0..10u8 {
+            big.apply(big.add(actor * 10 + member, big.read_ctx().derive_add_ctx(actor)));
+        }
+    }
+    let mut a = big.clone();
+    a.apply(a.rm(0, a.contains(&0).derive_rm_ctx()));
+    big.apply(big.rm(99, big.contains(&99).derive_rm_ctx()));
+    big.apply(big.add(100, big.read_ctx().derive_add_ctx(10)));
+
+    let mut full = a.clone();
+    full.merge(big.clone());
+
+    let mut calls = 0;
+    while a.merge_budgeted(&big, 1) == MergeProgress::Pending {
+        calls += 1;
+        assert!(a.contains(&50).val);
+    }
+    // actor 9 removed member 99, actor 10 added member 100
+    assert_eq!(calls, 1);
+    assert_eq!(a, full);
+    assert_eq!(a.read().val.len(), 99);
+    assert_eq!(a.merge_budgeted(&big, 0), MergeProgress::Complete);
+}
+
+#[test]
+fn test_add_if_absent() {
+    let mut a = Orswot::new();
+    let op = a.add_if_absent(1u8, 1u8).expect("1 is absent");
+    a.apply(op);
+    assert!(a.contains(&1).val);
+    assert_eq!(a.add_if_absent(1, 1), None);
+
+    a.apply(a.rm(1, a.contains(&1).derive_rm_ctx()));
+    assert!(a.add_if_absent(1, 1).is_some());
+}
@@ -3,6 +3,8 @@ extern crate quickcheck;
 
 extern crate crdts;
 
+#[cfg(feature = "testing")]
+mod laws;
 mod lseq;
 mod map;
 mod mvreg;
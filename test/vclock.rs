@@ -1,8 +1,22 @@
 use crdts::*;
 
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 
 quickcheck! {
+    fn prop_apply_dots_matches_apply(clock: VClock<u8>, dots: Vec<Dot<u8>>) -> bool {
+        let mut batched = clock.clone();
+        batched.apply_dots(dots.clone());
+
+        let mut applied = clock;
+        dots.into_iter().for_each(|dot| applied.apply(dot));
+        batched == applied
+    }
+
+    fn prop_pack_round_trips(clock: VClock<u8>) -> bool {
+        VClock::unpack(clock.pack()) == clock
+    }
+
     fn prop_into_iter_produces_same_vclock(clock: VClock<u8>) -> bool {
         clock == clock.clone().into_iter().collect()
     }
@@ -88,6 +102,94 @@ fn test_reset_remove() {
     assert_eq!(a, expected);
 }
 
+#[test]
+fn test_restrict_to() {
+    let clock: VClock<char> = vec![Dot::new('A', 1), Dot::new('B', 2), Dot::new('C', 3)]
+        .into_iter()
+        .collect();
+    let actors: BTreeSet<char> = vec!['A', 'C', 'D'].into_iter().collect();
+    let expected: VClock<char> = vec![Dot::new('A', 1), Dot::new('C', 3)]
+        .into_iter()
+        .collect();
+
+    assert_eq!(clock.restrict_to(&actors), expected);
+    assert_eq!(clock.get(&'B'), 2);
+}
+
+#[test]
+fn test_dominates_dot() {
+    let clock = VClock::from(Dot::new("A", 3));
+    assert!(clock.dominates_dot(&Dot::new("A", 2)));
+    assert!(clock.dominates_dot(&Dot::new("A", 3)));
+    assert!(!clock.dominates_dot(&Dot::new("A", 4)));
+    assert!(!clock.dominates_dot(&Dot::new("B", 1)));
+}
+
+#[test]
+fn test_causal_length() {
+    let clock: VClock<&str> = vec![Dot::new("A", 3), Dot::new("B", 2)]
+        .into_iter()
+        .collect();
+    assert_eq!(clock.causal_length(), 5);
+    assert_eq!(VClock::<&str>::new().causal_length(), 0);
+
+    let huge: VClock<&str> = vec![Dot::new("A", u64::MAX), Dot::new("B", 2)]
+        .into_iter()
+        .collect();
+    assert_eq!(huge.causal_length(), u64::MAX);
+}
+
+#[test]
+fn test_serde_json_roundtrip_with_integer_actors() {
+    let clock: VClock<u64> = vec![Dot::new(1, 4), Dot::new(2, 3)].into_iter().collect();
+    let json = serde_json::to_string(&clock).unwrap();
+    assert_eq!(
+        json,
+        r#"{"dots":[{"actor":1,"count":4},{"actor":2,"count":3}]}"#
+    );
+    assert_eq!(serde_json::from_str::<VClock<u64>>(&json).unwrap(), clock);
+
+    let mut counter: GCounter<u64> = GCounter::new();
+    counter.apply(counter.inc_many(7, 3));
+    let json = serde_json::to_string(&counter).unwrap();
+    assert_eq!(
+        serde_json::from_str::<GCounter<u64>>(&json).unwrap(),
+        counter
+    );
+}
+
+#[test]
+fn test_serde_json_roundtrip_with_tuple_actors() {
+    let clock: VClock<(u16, u16)> = vec![Dot::new((1, 2), 4), Dot::new((3, 4), 1)]
+        .into_iter()
+        .collect();
+    let json = serde_json::to_string(&clock).unwrap();
+    assert_eq!(
+        serde_json::from_str::<VClock<(u16, u16)>>(&json).unwrap(),
+        clock
+    );
+
+    let mut counter: PNCounter<(u16, u16)> = PNCounter::new();
+    counter.apply(counter.inc((1, 2)));
+    counter.apply(counter.dec((3, 4)));
+    let json = serde_json::to_string(&counter).unwrap();
+    assert_eq!(
+        serde_json::from_str::<PNCounter<(u16, u16)>>(&json).unwrap(),
+        counter
+    );
+}
+
+#[test]
+fn test_serde_json_decode_drops_zero_counts() {
+    let clock: VClock<u64> =
+        serde_json::from_str(r#"{"dots":[{"actor":1,"count":0},{"actor":2,"count":3}]}"#).unwrap();
+    assert_eq!(clock, VClock::from(Dot::new(2, 3)));
+    assert_eq!(clock.dots.len(), 1);
+
+    let old: VClock<u64> = serde_json::from_str(r#"{"dots":{"1":0,"2":3}}"#).unwrap();
+    assert_eq!(old, clock);
+}
+
 #[test]
 fn test_merge() {
     let mut a: VClock<u8> = vec![Dot::new(1, 1), Dot::new(4, 4)].into_iter().collect();
@@ -203,3 +305,143 @@ fn test_vclock_ordering() {
     assert!(!(a > b));
     assert_eq!(a, b);
 }
+
+#[test]
+fn test_apply_dots_takes_max_per_actor() {
+    let mut clock = VClock::from(Dot::new("C", 9));
+    clock.apply_dots(vec![
+        Dot::new("B", 2),
+        Dot::new("A", 5),
+        Dot::new("C", 3),
+        Dot::new("A", 1),
+        Dot::new("B", 7),
+        Dot::new("D", 0),
+        Dot::new("A", 4),
+    ]);
+
+    let expected: VClock<_> = vec![Dot::new("A", 5), Dot::new("B", 7), Dot::new("C", 9)]
+        .into_iter()
+        .collect();
+    assert_eq!(clock, expected);
+}
+
+#[test]
+fn test_actors_ahead_of() {
+    let a: VClock<_> = vec![Dot::new("A", 3), Dot::new("B", 1)]
+        .into_iter()
+        .collect();
+    let b: VClock<_> = vec![Dot::new("A", 1), Dot::new("B", 1), Dot::new("C", 2)]
+        .into_iter()
+        .collect();
+
+    assert_eq!(a.actors_ahead_of(&b), vec!["A"]);
+    assert_eq!(b.actors_ahead_of(&a), vec!["C"]);
+    assert!(a.actors_ahead_of(&a).is_empty());
+    assert_eq!(a.actors_ahead_of(&VClock::new()), vec!["A", "B"]);
+}
+
+#[test]
+fn test_happened_before_and_after() {
+    let a: VClock<_> = vec![Dot::new("A", 1), Dot::new("B", 1)]
+        .into_iter()
+        .collect();
+    let b: VClock<_> = vec![Dot::new("A", 2), Dot::new("B", 1)]
+        .into_iter()
+        .collect();
+    let c: VClock<_> = vec![Dot::new("A", 1), Dot::new("B", 2)]
+        .into_iter()
+        .collect();
+
+    assert!(a.happened_before(&b));
+    assert!(b.happened_after(&a));
+    assert!(!b.happened_before(&a));
+    assert!(!a.happened_after(&b));
+
+    assert!(!b.happened_before(&c));
+    assert!(!b.happened_after(&c));
+    assert!(b.concurrent(&c));
+
+    assert!(!a.happened_before(&a));
+    assert!(!a.happened_after(&a));
+    assert!(VClock::new().happened_before(&a));
+}
+
+#[test]
+fn test_fixed_vclock_merge_and_compare() {
+    use crdts::vclock::FixedVClock;
+
+    let mut a = FixedVClock::<3>::new();
+    let mut b = FixedVClock::<3>::new();
+    a.apply(a.inc(0));
+    a.apply(a.inc(0));
+    b.apply(b.inc(1));
+
+    assert_eq!(a.partial_cmp(&b), None);
+    assert_eq!(
+        b.validate_op(&Dot::new(2, 3)),
+        Err(CrdtError::DotGap(DotRange {
+            actor: 2,
+            counter_range: 1..3,
+        }))
+    );
+    assert_eq!(
+        b.validate_op(&Dot::new(3, 1)),
+        Err(CrdtError::UnknownActor { actor: 3 })
+    );
+
+    let mut merged = a;
+    merged.merge(b);
+    assert_eq!(merged.counters(), &[2, 1, 0]);
+    assert!(merged > a);
+    assert!(merged > b);
+    assert_eq!(merged.partial_cmp(&merged), Some(Ordering::Equal));
+
+    let expected: VClock<usize> = vec![Dot::new(0, 2), Dot::new(1, 1)].into_iter().collect();
+    assert_eq!(merged.to_vclock(), expected);
+
+    // merging the same clock again is a no-op
+    let before = merged;
+    merged.merge(b);
+    assert_eq!(merged, before);
+
+    assert_eq!(merged.checked_inc(1), Ok(Dot::new(1, 2)));
+    merged.apply(Dot::new(2, u64::MAX));
+    assert_eq!(merged.inc(2), Dot::new(2, u64::MAX));
+    assert_eq!(merged.checked_inc(2), Err(CrdtError::Overflow { actor: 2 }));
+}
+
+#[test]
+fn test_pack_and_unpack() {
+    let clock: VClock<_> = vec![Dot::new("C", 1), Dot::new("A", 3), Dot::new("B", 7)]
+        .into_iter()
+        .collect();
+    let packed = clock.pack();
+    assert_eq!(packed, vec![("A", 3), ("B", 7), ("C", 1)]);
+    assert_eq!(
+        VClock::unpack(packed).partial_cmp(&clock),
+        Some(Ordering::Equal)
+    );
+
+    // unpacking tolerates unsorted, repeated and zero entries
+    let unpacked = VClock::unpack(vec![("B", 7), ("A", 1), ("A", 3), ("C", 1), ("D", 0)]);
+    assert_eq!(unpacked, clock);
+}
+
+#[test]
+fn test_advance_to() {
+    let mut a: VClock<_> = vec![Dot::new("A", 2), Dot::new("B", 1)]
+        .into_iter()
+        .collect();
+    let b: VClock<_> = vec![Dot::new("A", 1), Dot::new("B", 3), Dot::new("C", 1)]
+        .into_iter()
+        .collect();
+
+    assert!(a.advance_to(&b));
+    assert!(a > b);
+    assert_eq!(a.get(&"A"), 2);
+    assert_eq!(a.get(&"B"), 3);
+
+    let same = a.clone();
+    assert!(!a.advance_to(&same));
+    assert_eq!(a, same);
+}